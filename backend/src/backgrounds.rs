@@ -0,0 +1,58 @@
+use std::{fs, io::Cursor, path::PathBuf};
+
+use anyhow::{Context, Result};
+
+use http::StatusCode;
+use shared::config::Config;
+
+use crate::router::HttpError;
+
+/// Directory inside `server.data_dir` holding the map images. The frontend embeds the same directory at build time.
+pub const IMAGES_DIR: &str = "assets/images";
+
+/// A map image as the frontend shows it, named after its file without the `.png` extension
+pub struct Background {
+    pub name: String,
+    pub size: [usize; 2],
+    pub data: Vec<u8>,
+}
+
+impl Background {
+    /// Path of the image of the map `name`, fails if there is no such map
+    pub fn path(config: &Config, name: &str) -> Result<PathBuf> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+            return Err(
+                HttpError::bad_request(format!("Invalid background name: {}", name)).into(),
+            );
+        }
+
+        let path = config.data_path(IMAGES_DIR).join(format!("{}.png", name));
+
+        if !path.exists() {
+            return Err(HttpError::new(
+                StatusCode::NOT_FOUND,
+                format!("Background not found: {}", name),
+            )
+            .into());
+        }
+
+        Ok(path)
+    }
+
+    pub fn load(config: &Config, name: &str) -> Result<Self> {
+        let path = Self::path(config, name)?;
+
+        let data = fs::read(&path).context(format!("Failed to read {}", path.display()))?;
+
+        let (width, height) = image::io::Reader::new(Cursor::new(&data))
+            .with_guessed_format()?
+            .into_dimensions()
+            .context(format!("Failed to read dimensions of {}", path.display()))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            size: [width as usize, height as usize],
+            data,
+        })
+    }
+}
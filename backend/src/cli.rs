@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use log::LevelFilter;
+use shared::config::{Config, DEFAULT_CONFIG_PATH};
+
+/// Synced drawing server
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Path to the configuration file
+    #[arg(short, long, global = true, default_value = DEFAULT_CONFIG_PATH)]
+    pub config: PathBuf,
+
+    /// Address to listen on, overrides `host.bind`
+    #[arg(short, long, global = true)]
+    pub bind: Option<String>,
+
+    /// Port to listen on, overrides `host.port`
+    #[arg(short, long, global = true)]
+    pub port: Option<u16>,
+
+    /// Directory containing `public/`, overrides `server.data_dir`
+    #[arg(short, long, global = true)]
+    pub data_dir: Option<PathBuf>,
+
+    /// Log level, overrides `server.log_level`
+    #[arg(short, long, global = true)]
+    pub log_level: Option<LevelFilter>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Start the server (default)
+    Run,
+    /// Write the default configuration file
+    Init {
+        /// Overwrite an existing configuration file
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Load and print the configuration without starting the server
+    CheckConfig,
+}
+
+impl Cli {
+    /// Applies the command-line flags on top of the values from the configuration file
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(bind) = &self.bind {
+            config.host.bind = bind.clone();
+        }
+
+        if let Some(port) = self.port {
+            config.host.port = port;
+        }
+
+        if let Some(data_dir) = &self.data_dir {
+            config.server.data_dir = data_dir.clone();
+        }
+
+        if let Some(log_level) = self.log_level {
+            config.server.log_level = log_level.to_string().to_lowercase();
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt::Write;
+
+use base64::Engine;
+use egui::Rect;
+use shared::{background_canvas_rect, shape::stroke_sides, style::Cap, text::Text, Line, Lines};
+
+use crate::backgrounds::Background;
+
+/// Renders `lines` as an SVG document in canvas coordinates.
+/// With a background the view box is the area of the map, otherwise it is the bounding box of the lines.
+pub fn to_svg(lines: &Lines, background: Option<&Background>, embed_background: bool) -> String {
+    let view_box = view_box(lines, background);
+
+    let mut svg = String::new();
+
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        view_box.min.x,
+        view_box.min.y,
+        view_box.width(),
+        view_box.height(),
+        view_box.width(),
+        view_box.height()
+    );
+
+    if let (Some(background), true) = (background, embed_background) {
+        let rect = background_canvas_rect(background.size);
+
+        let _ = writeln!(
+            svg,
+            r#"  <image href="data:image/png;base64,{}" x="{}" y="{}" width="{}" height="{}" preserveAspectRatio="none"/>"#,
+            base64::engine::general_purpose::STANDARD.encode(&background.data),
+            rect.min.x,
+            rect.min.y,
+            rect.width(),
+            rect.height()
+        );
+    }
+
+    for element in sorted_lines(lines).into_iter().flat_map(line_elements) {
+        let _ = writeln!(svg, "  {}", element);
+    }
+
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// Area of the map if there is a background, otherwise the bounding box of the lines
+pub fn view_box(lines: &Lines, background: Option<&Background>) -> Rect {
+    match background {
+        Some(background) => background_canvas_rect(background.size),
+        None => lines
+            .bounding_rect()
+            .unwrap_or(background_canvas_rect([1, 1])),
+    }
+}
+
+/// Drawable lines in paint order: highlighter strokes first, otherwise by id, so that repeated exports of the same board are identical
+pub fn sorted_lines(lines: &Lines) -> Vec<&Line> {
+    let mut line_ids: Vec<&usize> = lines.keys().collect();
+    line_ids.sort_by_key(|line_id| (!lines[line_id].style.highlighter, *line_id));
+
+    line_ids
+        .into_iter()
+        .map(|line_id| &lines[line_id])
+        .filter(|line| line.is_drawable())
+        .collect()
+}
+
+/// One `polyline` or `polygon` per path of the line, the first one carries the fill.
+/// Text is a single `text` element, strokes with pressure are a filled outline per dash.
+fn line_elements(line: &Line) -> Vec<String> {
+    let [r, g, b, a] = line
+        .style
+        .paint_color(line.stroke.color)
+        .to_srgba_unmultiplied();
+
+    if let Some(text) = line.text() {
+        return vec![text_element(
+            text,
+            &format!(
+                r#"fill="rgb({},{},{})" fill-opacity="{}""#,
+                r,
+                g,
+                b,
+                a as f32 / 255.0
+            ),
+        )];
+    }
+
+    if let Some(parts) = line.pressure_parts() {
+        return parts
+            .into_iter()
+            .map(|(points, widths)| {
+                let (left, right) = stroke_sides(&points, &widths);
+
+                let outline = left
+                    .iter()
+                    .chain(right.iter().rev())
+                    .map(|p| format!("{},{}", p.x, p.y))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+
+                format!(
+                    r#"<polygon points="{}" fill="rgb({},{},{})" fill-opacity="{}"/>"#,
+                    outline,
+                    r,
+                    g,
+                    b,
+                    a as f32 / 255.0
+                )
+            })
+            .collect();
+    }
+
+    let line_cap = match line.style.paint_cap() {
+        Cap::Butt => "butt",
+        Cap::Round => "round",
+        Cap::Square => "square",
+    };
+
+    let dash_array = match line.style.dash_array(line.stroke.width) {
+        Some([dash, gap]) => format!(r#" stroke-dasharray="{} {}""#, dash, gap),
+        None => String::new(),
+    };
+
+    let fill = match line.fill() {
+        Some(fill) => {
+            let [r, g, b, a] = line.style.paint_color(fill).to_srgba_unmultiplied();
+
+            format!(
+                r#"fill="rgb({},{},{})" fill-opacity="{}" fill-rule="evenodd""#,
+                r,
+                g,
+                b,
+                a as f32 / 255.0
+            )
+        }
+        None => r#"fill="none""#.to_string(),
+    };
+
+    line.paths()
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let points = path
+                .points
+                .iter()
+                .map(|p| format!("{},{}", p.x, p.y))
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            format!(
+                r#"<{} points="{}" {} stroke="rgb({},{},{})" stroke-opacity="{}" stroke-width="{}" stroke-linecap="{}" stroke-linejoin="miter"{}/>"#,
+                if path.closed { "polygon" } else { "polyline" },
+                points,
+                if i == 0 { fill.as_str() } else { r#"fill="none""# },
+                r,
+                g,
+                b,
+                a as f32 / 255.0,
+                line.stroke.width,
+                line_cap,
+                dash_array
+            )
+        })
+        .collect()
+}
+
+/// One `tspan` per row, positioned on the baselines the server and the frontend lay out
+fn text_element(text: &Text, fill: &str) -> String {
+    let rows: String = text
+        .rows()
+        .enumerate()
+        .map(|(row, content)| {
+            format!(
+                r#"<tspan x="{}" y="{}">{}</tspan>"#,
+                text.anchor.0.x,
+                text.anchor.0.y + text.ascent() + row as f32 * text.row_height(),
+                escape_xml(content)
+            )
+        })
+        .collect();
+
+    format!(
+        r#"<text font-family="Ubuntu, sans-serif" font-weight="300" font-size="{}" {} xml:space="preserve">{}</text>"#,
+        text.font_size, fill, rows
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
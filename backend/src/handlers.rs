@@ -0,0 +1,408 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use anyhow::Result;
+
+use egui::{Pos2, Rect, Vec2};
+use http::StatusCode;
+use log::{debug, info};
+use shared::{
+    grid::Grid,
+    snapshot::{LoadMode, Snapshot},
+    ChangedLines, ClientID, Flag, Message,
+};
+
+use crate::{
+    backgrounds::Background,
+    export, import,
+    raster::{self, RasterOptions},
+    response::Response,
+    router::{Context, HttpError, Router},
+    state::Client,
+};
+
+pub fn routes() -> Router {
+    Router::new()
+        .get("/", index)
+        .get("/wasm/frontend.js", frontend_js)
+        .get("/wasm/frontend_bg.wasm", frontend_wasm)
+        .get("/hello", hello)
+        .get("/get_lines", get_lines)
+        .post("/send_lines", send_lines)
+        .post("/delete_lines", delete_lines)
+        .post("/clear_lines", clear_lines)
+        .get("/export/svg", export_svg)
+        .get("/export/png", export_png)
+        .post("/import/svg", import_svg)
+        .get("/snapshot", download_snapshot)
+        .post("/snapshot", upload_snapshot)
+        .get("/grids", get_grids)
+        .post("/grids/:background", send_grid)
+}
+
+fn index(context: &mut Context) -> Result<Response> {
+    let config = context.config;
+
+    let host = match context.request.peer.ip()? {
+        "127.0.0.1" => format!("127.0.0.1:{}", config.host.port),
+        _ => format!("{}:{}", config.host.ip, config.host.port),
+    };
+
+    let replace_content = [
+        ["#host".to_string(), host],
+        ["#title".to_string(), config.website.title.clone()],
+    ];
+
+    Response::html_template(config.data_path("public/index.html"), &replace_content)
+}
+
+fn frontend_js(context: &mut Context) -> Result<Response> {
+    Response::file(
+        context.config.data_path("public/wasm/frontend.js"),
+        "text/javascript",
+    )
+}
+
+fn frontend_wasm(context: &mut Context) -> Result<Response> {
+    Response::file(
+        context.config.data_path("public/wasm/frontend_bg.wasm"),
+        "application/wasm",
+    )
+}
+
+fn hello(context: &mut Context) -> Result<Response> {
+    let peer_ip = context.request.peer.ip()?;
+
+    let state = &mut *context.state;
+
+    let client_id = match state.clients.get(peer_ip) {
+        Some(client) => client.id,
+        None => {
+            let client_id = ClientID::new();
+
+            state
+                .clients
+                .insert(peer_ip.to_string(), Client { id: client_id });
+
+            state
+                .changed_lines_sync
+                .insert(client_id, ChangedLines::default());
+
+            client_id
+        }
+    };
+
+    info!("Client {} connected from {}", client_id.0, peer_ip);
+
+    debug!("Current clients: {:?}", state.clients.keys());
+    debug!("Current changed lines: {:?}", state.changed_lines_sync);
+
+    Response::json(&client_id)
+}
+
+fn send_lines(context: &mut Context) -> Result<Response> {
+    let content = context.request.body_str()?;
+
+    let message = serde_json::from_str::<Message>(content).map_err(|e| {
+        HttpError::bad_request(format!(
+            "Failed to parse message: {} - content: {}",
+            e, content
+        ))
+    })?;
+
+    let state = &mut *context.state;
+
+    let other_lines = message.lines;
+    let changed_lines = message.changed_lines;
+
+    debug!("Received lines: {:?}", other_lines.keys());
+    debug!("Current lines: {:?}", state.lines.keys());
+
+    let max_lines = context.config.limits.max_lines;
+
+    let new_lines = other_lines
+        .keys()
+        .filter(|line_id| !state.lines.contains_key(line_id))
+        .count();
+
+    if state.lines.len() + new_lines > max_lines {
+        return Err(HttpError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Board is limited to {} lines", max_lines),
+        )
+        .into());
+    }
+
+    state.lines.merge(other_lines, &changed_lines);
+
+    if let Some(changed_lines) = changed_lines {
+        state
+            .changed_lines_sync
+            .values_mut()
+            .for_each(|changed_lines_l| {
+                changed_lines_l.0.extend(changed_lines.0.iter());
+            });
+    }
+
+    Ok(Response::empty())
+}
+
+fn delete_lines(context: &mut Context) -> Result<Response> {
+    let content = context.request.body_str()?;
+
+    let changed_lines = serde_json::from_str::<ChangedLines>(content).map_err(|e| {
+        HttpError::bad_request(format!(
+            "Failed to parse changed lines: {} - content: {}",
+            e, content
+        ))
+    })?;
+
+    let state = &mut *context.state;
+
+    for line_id in changed_lines.0.iter() {
+        debug!("Deleting line: {}", line_id);
+        state.lines.0.remove(line_id);
+        debug!("Current lines: {:?}", state.lines.0.keys());
+    }
+
+    let changed_lines_sync = &mut state.changed_lines_sync;
+
+    state.clients.values().for_each(|client| {
+        changed_lines_sync
+            .entry(client.id)
+            .or_default()
+            .0
+            .extend(changed_lines.0.iter());
+    });
+
+    debug!("Changed lines: {:?}", changed_lines_sync);
+
+    Ok(Response::empty())
+}
+
+fn get_lines(context: &mut Context) -> Result<Response> {
+    let client_id = context.require_client_id()?;
+
+    let state = &mut *context.state;
+
+    let changed_lines = state.changed_lines_sync.remove(&client_id);
+
+    let mut flag: Option<Flag> = None;
+
+    if let Some(clear_sync) = state.clear_sync.as_mut() {
+        if clear_sync.remove(&client_id) {
+            flag = Some(Flag::Clear);
+        }
+    }
+
+    let message = Message {
+        lines: state.lines.clone(),
+        changed_lines,
+        flag,
+    };
+
+    Response::json(&message)
+}
+
+fn clear_lines(context: &mut Context) -> Result<Response> {
+    let state = &mut *context.state;
+
+    state.lines.clear();
+
+    state.changed_lines_sync = HashMap::new();
+
+    state.clear_sync = Some(state.clients.values().map(|client| client.id).collect());
+
+    Ok(Response::empty())
+}
+
+/// `GET /export/svg?background=<name>&embed_background=<bool>`
+fn export_svg(context: &mut Context) -> Result<Response> {
+    let query = &context.request.query;
+
+    let background = match query.get("background") {
+        Some(name) => Some(Background::load(context.config, name)?),
+        None => None,
+    };
+
+    let embed_background = query.parse::<bool>("embed_background")?.unwrap_or(true);
+
+    let svg = export::to_svg(&context.state.lines, background.as_ref(), embed_background);
+
+    Ok(Response::ok("image/svg+xml", svg))
+}
+
+/// `GET /export/png?background=<name>&width=<px>&height=<px>&crop=<x>,<y>,<width>,<height>`
+fn export_png(context: &mut Context) -> Result<Response> {
+    let query = &context.request.query;
+
+    let background = match query.get("background") {
+        Some(name) => Some(Background::load(context.config, name)?),
+        None => None,
+    };
+
+    let crop = match query.get("crop") {
+        Some(crop) => Some(parse_rect(crop)?),
+        None => None,
+    };
+
+    let options = RasterOptions {
+        crop,
+        width: query.parse::<u32>("width")?,
+        height: query.parse::<u32>("height")?,
+    };
+
+    let png = raster::to_png(&context.state.lines, background.as_ref(), options)?;
+
+    Ok(Response::ok("image/png", png))
+}
+
+/// Parses `x,y,width,height`
+fn parse_rect(value: &str) -> Result<Rect> {
+    let numbers = value
+        .split(',')
+        .map(|number| number.trim().parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|e| HttpError::bad_request(format!("Invalid rectangle {}: {}", value, e)))?;
+
+    match numbers[..] {
+        [x, y, width, height] => Ok(Rect::from_min_size(
+            Pos2::new(x, y),
+            Vec2::new(width, height),
+        )),
+        _ => Err(
+            HttpError::bad_request(format!("Expected x,y,width,height but got {}", value)).into(),
+        ),
+    }
+}
+
+/// `POST /import/svg` with an SVG document as body. Adds its shapes as new lines and responds with their ids.
+fn import_svg(context: &mut Context) -> Result<Response> {
+    let svg = context.request.body_str()?;
+
+    let lines = import::svg_to_lines(svg)?;
+
+    let state = &mut *context.state;
+
+    let max_lines = context.config.limits.max_lines;
+
+    if state.lines.len() + lines.len() > max_lines {
+        return Err(HttpError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Board is limited to {} lines", max_lines),
+        )
+        .into());
+    }
+
+    let line_ids: Vec<usize> = lines
+        .into_iter()
+        .map(|line| {
+            let line_id = rand::random::<usize>();
+            state.lines.0.insert(line_id, line);
+            line_id
+        })
+        .collect();
+
+    info!("Imported {} lines from SVG", line_ids.len());
+
+    Response::json(&line_ids)
+}
+
+/// `GET /snapshot?background=<name>&download=<bool>` responds with the board as [`Snapshot`]
+fn download_snapshot(context: &mut Context) -> Result<Response> {
+    let query = &context.request.query;
+
+    let created = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+
+    let snapshot = Snapshot::new(
+        context.config.website.title.clone(),
+        created,
+        query.get("background").map(str::to_string),
+        context.state.lines.clone(),
+    );
+
+    let response = Response::ok("application/json", snapshot.to_json()?);
+
+    match query.parse::<bool>("download")?.unwrap_or(false) {
+        true => Ok(response.as_attachment(&format!("board-{}.json", created))),
+        false => Ok(response),
+    }
+}
+
+/// `POST /snapshot?mode=<replace|merge>` with a [`Snapshot`] as body. Responds with the background of the snapshot.
+fn upload_snapshot(context: &mut Context) -> Result<Response> {
+    let mode = match context.request.query.get("mode") {
+        None | Some("replace") => LoadMode::Replace,
+        Some("merge") => LoadMode::Merge,
+        Some(mode) => {
+            return Err(HttpError::bad_request(format!("Unknown load mode: {}", mode)).into())
+        }
+    };
+
+    let snapshot = Snapshot::from_json(context.request.body_str()?)
+        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+
+    let state = &mut *context.state;
+
+    let max_lines = context.config.limits.max_lines;
+
+    let line_count = match mode {
+        LoadMode::Replace => snapshot.lines.len(),
+        LoadMode::Merge => state.lines.len() + snapshot.lines.len(),
+    };
+
+    if line_count > max_lines {
+        return Err(HttpError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Board is limited to {} lines", max_lines),
+        )
+        .into());
+    }
+
+    let background = snapshot.background.clone();
+
+    let line_ids = snapshot.apply(&mut state.lines, mode);
+
+    // like clear_lines, clients drop their lines and pick up the snapshot with their next poll
+    if mode == LoadMode::Replace {
+        state.changed_lines_sync = HashMap::new();
+        state.clear_sync = Some(state.clients.values().map(|client| client.id).collect());
+    }
+
+    info!("Loaded {} lines from snapshot ({:?})", line_ids.len(), mode);
+
+    Response::json(&background)
+}
+
+/// `GET /grids` responds with the grid of every map that has one, by map name
+fn get_grids(context: &mut Context) -> Result<Response> {
+    Response::json(&context.state.grids)
+}
+
+/// `POST /grids/<background>` with a [`Grid`] as body replaces the grid of the map
+fn send_grid(context: &mut Context) -> Result<Response> {
+    let background = match context.params.get("background") {
+        Some(background) => background.to_string(),
+        None => return Err(HttpError::bad_request("Missing background").into()),
+    };
+
+    Background::path(context.config, &background)?;
+
+    let content = context.request.body_str()?;
+
+    let grid = serde_json::from_str::<Grid>(content).map_err(|e| {
+        HttpError::bad_request(format!(
+            "Failed to parse grid: {} - content: {}",
+            e, content
+        ))
+    })?;
+
+    if !grid.is_valid() {
+        return Err(HttpError::bad_request(format!("Invalid grid: {:?}", grid)).into());
+    }
+
+    context.state.grids.insert(background, grid);
+
+    Ok(Response::empty())
+}
@@ -0,0 +1,411 @@
+use anyhow::Result;
+
+use egui::Pos2;
+use kurbo::{Affine, BezPath, PathEl, Point, Rect, Shape};
+use shared::{color::Color, shape::ShapeKind, style::StrokeStyle, Line, SPos2, StrokeX};
+
+use crate::router::HttpError;
+
+/// Maximum distance in canvas units between a curve and the polyline that replaces it
+const FLATTEN_TOLERANCE: f64 = 0.25;
+
+/// Shapes further from the origin are rejected, flattening huge curves would take forever
+const MAX_COORDINATE: f64 = 1e6;
+
+/// Points of all lines of a document together, huge curves flatten into that many
+const MAX_POINTS: usize = 200_000;
+
+/// Presentation attributes that are inherited from parent elements
+#[derive(Debug, Clone)]
+struct Style {
+    stroke: Option<Color>,
+    fill: Option<Color>,
+    stroke_width: f64,
+    opacity: f64,
+    stroke_opacity: f64,
+    fill_opacity: f64,
+    transform: Affine,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            stroke: None,
+            fill: Some(Color::BLACK),
+            stroke_width: 1.0,
+            opacity: 1.0,
+            stroke_opacity: 1.0,
+            fill_opacity: 1.0,
+            transform: Affine::IDENTITY,
+        }
+    }
+}
+
+/// Converts the `path`, `polyline`, `polygon`, `line` and `rect` elements of an SVG document into lines.
+/// Every subpath becomes its own line, curves are flattened and the user units of the document are used as canvas coordinates.
+/// Documents that are not SVG, invalid path data, shapes far off the canvas and too many points fail with `400 Bad Request`.
+pub fn svg_to_lines(svg: &str) -> Result<Vec<Line>> {
+    let document = roxmltree::Document::parse(svg)
+        .map_err(|e| HttpError::bad_request(format!("Failed to parse SVG: {}", e)))?;
+
+    let root = document.root_element();
+
+    if root.tag_name().name() != "svg" {
+        return Err(HttpError::bad_request(format!(
+            "Expected an svg document, found <{}>",
+            root.tag_name().name()
+        ))
+        .into());
+    }
+
+    let mut lines = Vec::new();
+
+    let mut points_left = MAX_POINTS;
+
+    collect_lines(root, &Style::default(), &mut lines, &mut points_left)?;
+
+    Ok(lines)
+}
+
+fn collect_lines(
+    node: roxmltree::Node,
+    parent_style: &Style,
+    lines: &mut Vec<Line>,
+    points_left: &mut usize,
+) -> Result<()> {
+    let style = element_style(node, parent_style);
+
+    let path = match node.tag_name().name() {
+        "path" => match node.attribute("d") {
+            Some(d) => Some(BezPath::from_svg(d).map_err(|e| {
+                HttpError::bad_request(format!("Invalid path data {:?}: {}", d, e))
+            })?),
+            None => None,
+        },
+        "polyline" => node
+            .attribute("points")
+            .map(|points| points_path(points, false)),
+        "polygon" => node
+            .attribute("points")
+            .map(|points| points_path(points, true)),
+        "line" => {
+            let mut path = BezPath::new();
+            path.move_to((number(node, "x1"), number(node, "y1")));
+            path.line_to((number(node, "x2"), number(node, "y2")));
+            Some(path)
+        }
+        "rect" => {
+            let rect = Rect::from_origin_size(
+                (number(node, "x"), number(node, "y")),
+                (number(node, "width"), number(node, "height")),
+            );
+            Some(rect.to_path(FLATTEN_TOLERANCE))
+        }
+        "defs" | "clipPath" | "mask" | "symbol" | "marker" | "pattern" => return Ok(()),
+        _ => None,
+    };
+
+    if let Some(path) = path {
+        lines.extend(path_to_lines(&path, &style, points_left)?);
+    }
+
+    for child in node.children().filter(|child| child.is_element()) {
+        collect_lines(child, &style, lines, points_left)?;
+    }
+
+    Ok(())
+}
+
+/// `points_left` is what is left of the point budget of the document
+fn path_to_lines(path: &BezPath, style: &Style, points_left: &mut usize) -> Result<Vec<Line>> {
+    // shapes without stroke are outlined with their fill
+    let (color, paint_opacity) = match (style.stroke, style.fill) {
+        (Some(stroke), _) => (stroke, style.stroke_opacity),
+        (None, Some(fill)) => (fill, style.fill_opacity),
+        (None, None) => return Ok(Vec::new()),
+    };
+
+    let path = style.transform * path.clone();
+
+    let bounds = path.bounding_box();
+
+    // NaN fails the comparison as well
+    if ![bounds.x0, bounds.y0, bounds.x1, bounds.y1]
+        .iter()
+        .all(|coordinate| coordinate.abs() <= MAX_COORDINATE)
+    {
+        return Err(HttpError::bad_request(format!(
+            "Shape at {:?} is out of range, coordinates are limited to {}",
+            bounds, MAX_COORDINATE
+        ))
+        .into());
+    }
+
+    let alpha = (style.opacity * paint_opacity).clamp(0.0, 1.0);
+
+    let color = Color {
+        a: (color.a as f64 * alpha).round() as u8,
+        ..color
+    };
+
+    // average scale of the transform, so that the stroke width follows scaled groups
+    let scale = style.transform.determinant().abs().sqrt();
+
+    let width = (style.stroke_width * scale) as f32;
+
+    if !(width.is_finite() && width >= 0.0) {
+        return Err(
+            HttpError::bad_request(format!("Invalid stroke width {}", style.stroke_width)).into(),
+        );
+    }
+
+    let stroke = StrokeX::new(width, color);
+
+    let mut lines = Vec::new();
+    let mut coordinates: Vec<SPos2> = Vec::new();
+    let mut subpath_start: Option<Point> = None;
+
+    let mut finish_subpath = |coordinates: &mut Vec<SPos2>| {
+        if coordinates.len() >= 2 {
+            lines.push(Line {
+                coordinates: std::mem::take(coordinates),
+                stroke,
+                style: StrokeStyle::default(),
+                shape: ShapeKind::Freehand,
+                pressure: Vec::new(),
+                flag: None,
+            });
+        }
+        coordinates.clear();
+    };
+
+    let to_spos2 = |point: Point| SPos2(Pos2::new(point.x as f32, point.y as f32));
+
+    let mut is_over_budget = false;
+
+    path.flatten(FLATTEN_TOLERANCE, |element| {
+        // flatten can not be stopped, the points past the budget are dropped
+        if is_over_budget {
+            return;
+        }
+
+        match *points_left {
+            0 => {
+                is_over_budget = true;
+                return;
+            }
+            _ => *points_left -= 1,
+        }
+
+        match element {
+            PathEl::MoveTo(point) => {
+                finish_subpath(&mut coordinates);
+                subpath_start = Some(point);
+                coordinates.push(to_spos2(point));
+            }
+            PathEl::LineTo(point) => coordinates.push(to_spos2(point)),
+            PathEl::ClosePath => {
+                if let Some(start) = subpath_start {
+                    coordinates.push(to_spos2(start));
+                }
+            }
+            // flatten only emits lines
+            PathEl::QuadTo(_, point) | PathEl::CurveTo(_, _, point) => {
+                coordinates.push(to_spos2(point))
+            }
+        }
+    });
+
+    if is_over_budget {
+        return Err(HttpError::bad_request(format!(
+            "The shapes flatten into more than {} points",
+            MAX_POINTS
+        ))
+        .into());
+    }
+
+    finish_subpath(&mut coordinates);
+
+    Ok(lines)
+}
+
+fn points_path(points: &str, close: bool) -> BezPath {
+    let numbers = numbers(points);
+
+    let mut path = BezPath::new();
+
+    for (i, pair) in numbers.chunks_exact(2).enumerate() {
+        match i {
+            0 => path.move_to((pair[0], pair[1])),
+            _ => path.line_to((pair[0], pair[1])),
+        }
+    }
+
+    if close {
+        path.close_path();
+    }
+
+    path
+}
+
+fn number(node: roxmltree::Node, attribute: &str) -> f64 {
+    node.attribute(attribute)
+        .and_then(parse_length)
+        .unwrap_or(0.0)
+}
+
+/// Parses a number and ignores a trailing unit like `px`
+fn parse_length(value: &str) -> Option<f64> {
+    let value = value.trim();
+
+    let end = value
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E')))
+        .unwrap_or(value.len());
+
+    value[..end].parse::<f64>().ok()
+}
+
+fn numbers(value: &str) -> Vec<f64> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|number| !number.is_empty())
+        .filter_map(|number| number.parse::<f64>().ok())
+        .collect()
+}
+
+fn element_style(node: roxmltree::Node, parent_style: &Style) -> Style {
+    let mut style = parent_style.clone();
+
+    // the style attribute takes precedence over presentation attributes
+    let mut properties: Vec<(String, String)> = node
+        .attributes()
+        .map(|attribute| (attribute.name().to_string(), attribute.value().to_string()))
+        .collect();
+
+    if let Some(css) = node.attribute("style") {
+        properties.extend(css.split(';').filter_map(|declaration| {
+            declaration
+                .split_once(':')
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        }));
+    }
+
+    for (key, value) in properties {
+        match key.as_str() {
+            "stroke" => style.stroke = parse_paint(&value, style.stroke),
+            "fill" => style.fill = parse_paint(&value, style.fill),
+            "stroke-width" => {
+                if let Some(width) = parse_length(&value) {
+                    style.stroke_width = width;
+                }
+            }
+            "opacity" => {
+                if let Some(opacity) = parse_length(&value) {
+                    style.opacity *= opacity;
+                }
+            }
+            "stroke-opacity" => {
+                if let Some(opacity) = parse_length(&value) {
+                    style.stroke_opacity = opacity;
+                }
+            }
+            "fill-opacity" => {
+                if let Some(opacity) = parse_length(&value) {
+                    style.fill_opacity = opacity;
+                }
+            }
+            "transform" => style.transform = parent_style.transform * parse_transform(&value),
+            _ => (),
+        }
+    }
+
+    style
+}
+
+/// `none` clears the paint, unknown values keep the inherited one
+fn parse_paint(value: &str, inherited: Option<Color>) -> Option<Color> {
+    match value.trim() {
+        "none" | "transparent" => None,
+        value => parse_color(value).or(inherited),
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim().to_lowercase();
+
+    if value.starts_with('#') {
+        return value.parse().ok();
+    }
+
+    if let Some(arguments) = value
+        .strip_prefix("rgb(")
+        .and_then(|value| value.strip_suffix(')'))
+    {
+        let channels: Vec<u8> = arguments
+            .split(',')
+            .filter_map(|channel| {
+                let channel = channel.trim();
+
+                match channel.strip_suffix('%') {
+                    Some(percent) => percent
+                        .parse::<f32>()
+                        .ok()
+                        .map(|percent| (percent * 2.55).round() as u8),
+                    None => channel.parse::<f32>().ok().map(|channel| channel as u8),
+                }
+            })
+            .collect();
+
+        return match channels[..] {
+            [r, g, b] => Some(Color::from_rgb(r, g, b)),
+            _ => None,
+        };
+    }
+
+    match value.as_str() {
+        "black" => Some(Color::BLACK),
+        "white" => Some(Color::from_rgb(255, 255, 255)),
+        "red" => Some(Color::from_rgb(255, 0, 0)),
+        "green" => Some(Color::from_rgb(0, 128, 0)),
+        "lime" => Some(Color::from_rgb(0, 255, 0)),
+        "blue" => Some(Color::from_rgb(0, 0, 255)),
+        "yellow" => Some(Color::from_rgb(255, 255, 0)),
+        "orange" => Some(Color::from_rgb(255, 165, 0)),
+        "purple" => Some(Color::from_rgb(128, 0, 128)),
+        "gray" | "grey" => Some(Color::from_rgb(128, 128, 128)),
+        _ => None,
+    }
+}
+
+/// Parses a transform list like `translate(10 20) scale(2)`
+fn parse_transform(value: &str) -> Affine {
+    let mut affine = Affine::IDENTITY;
+
+    for part in value.split(')') {
+        let (name, arguments) = match part.split_once('(') {
+            Some((name, arguments)) => (name.trim().trim_start_matches(',').trim(), arguments),
+            None => continue,
+        };
+
+        let arguments = numbers(arguments);
+
+        let transform = match (name, arguments.as_slice()) {
+            ("matrix", &[a, b, c, d, e, f]) => Affine::new([a, b, c, d, e, f]),
+            ("translate", &[x]) => Affine::translate((x, 0.0)),
+            ("translate", &[x, y]) => Affine::translate((x, y)),
+            ("scale", &[s]) => Affine::scale(s),
+            ("scale", &[x, y]) => Affine::scale_non_uniform(x, y),
+            ("rotate", &[angle]) => Affine::rotate(angle.to_radians()),
+            ("rotate", &[angle, x, y]) => {
+                Affine::rotate_about(angle.to_radians(), Point::new(x, y))
+            }
+            ("skewX", &[angle]) => Affine::skew(angle.to_radians().tan(), 0.0),
+            ("skewY", &[angle]) => Affine::skew(0.0, angle.to_radians().tan()),
+            _ => continue,
+        };
+
+        affine *= transform;
+    }
+
+    affine
+}
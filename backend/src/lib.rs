@@ -0,0 +1,13 @@
+pub mod backgrounds;
+pub mod export;
+pub mod handlers;
+pub mod import;
+pub mod raster;
+pub mod reload;
+pub mod request;
+pub mod response;
+pub mod router;
+pub mod server;
+pub mod state;
+
+pub use server::{RunningServer, Server, ShutdownHandle};
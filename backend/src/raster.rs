@@ -0,0 +1,215 @@
+use anyhow::{Context, Result};
+
+use egui::Rect;
+use http::StatusCode;
+use shared::{shape::stroke_sides, style::Cap, text::Curve, Lines};
+use tiny_skia::{
+    FillRule, FilterQuality, LineCap, LineJoin, Paint, PathBuilder, Pixmap, PixmapPaint, Stroke,
+    StrokeDash, Transform,
+};
+
+use crate::{
+    backgrounds::Background,
+    export::{sorted_lines, view_box},
+    router::HttpError,
+};
+
+/// Largest width or height of a rendered image in pixels
+pub const MAX_DIMENSION: u32 = 8192;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RasterOptions {
+    /// Area in canvas coordinates to render. Defaults to the same view box as the SVG export.
+    pub crop: Option<Rect>,
+    /// Output width in pixels. If only one of width and height is given, the other keeps the aspect ratio of the crop.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Composites the background and all lines into a PNG, the same way the egui painter draws them
+pub fn to_png(
+    lines: &Lines,
+    background: Option<&Background>,
+    options: RasterOptions,
+) -> Result<Vec<u8>> {
+    let crop = options.crop.unwrap_or(view_box(lines, background));
+
+    if !(crop.width() > 0.0 && crop.height() > 0.0) {
+        return Err(HttpError::bad_request("Crop rectangle must not be empty").into());
+    }
+
+    let aspect_ratio = crop.width() / crop.height();
+
+    let (width, height) = match (options.width, options.height) {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, (width as f32 / aspect_ratio).round() as u32),
+        (None, Some(height)) => ((height as f32 * aspect_ratio).round() as u32, height),
+        (None, None) => (crop.width().round() as u32, crop.height().round() as u32),
+    };
+
+    if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(HttpError::bad_request(format!(
+            "Image size {}x{} must be between 1x1 and {}x{}",
+            width, height, MAX_DIMENSION, MAX_DIMENSION
+        ))
+        .into());
+    }
+
+    let mut pixmap = Pixmap::new(width, height).context("Failed to allocate image")?;
+
+    let scale_x = width as f32 / crop.width();
+    let scale_y = height as f32 / crop.height();
+
+    let transform = Transform::from_row(
+        scale_x,
+        0.0,
+        0.0,
+        scale_y,
+        -crop.min.x * scale_x,
+        -crop.min.y * scale_y,
+    );
+
+    if let Some(background) = background {
+        let background_pixmap = Pixmap::decode_png(&background.data)
+            .map_err(|e| HttpError::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+        // the background covers `background_canvas_rect`, which starts at the origin with one canvas unit per pixel
+        pixmap.draw_pixmap(
+            0,
+            0,
+            background_pixmap.as_ref(),
+            &PixmapPaint {
+                quality: FilterQuality::Bilinear,
+                ..Default::default()
+            },
+            transform,
+            None,
+        );
+    }
+
+    for line in sorted_lines(lines) {
+        let [r, g, b, a] = line
+            .style
+            .paint_color(line.stroke.color)
+            .to_srgba_unmultiplied();
+
+        let mut paint = Paint::default();
+        paint.set_color_rgba8(r, g, b, a);
+        paint.anti_alias = true;
+
+        if let Some(text) = line.text() {
+            let mut path_builder = PathBuilder::new();
+
+            let mut end = None;
+
+            for curve in text.outline() {
+                if end != Some(curve.start()) {
+                    let start = curve.start();
+                    path_builder.move_to(start.x, start.y);
+                }
+
+                end = Some(match curve {
+                    Curve::Line(_, b) => {
+                        path_builder.line_to(b.x, b.y);
+                        b
+                    }
+                    Curve::Quad(_, b, c) => {
+                        path_builder.quad_to(b.x, b.y, c.x, c.y);
+                        c
+                    }
+                    Curve::Cubic(_, b, c, d) => {
+                        path_builder.cubic_to(b.x, b.y, c.x, c.y, d.x, d.y);
+                        d
+                    }
+                });
+            }
+
+            if let Some(path) = path_builder.finish() {
+                pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+            }
+
+            continue;
+        }
+
+        if let Some(parts) = line.pressure_parts() {
+            let mut path_builder = PathBuilder::new();
+
+            // every dash is an outline of its own
+            for (points, widths) in parts {
+                let (left, right) = stroke_sides(&points, &widths);
+
+                for (i, point) in left.iter().chain(right.iter().rev()).enumerate() {
+                    match i {
+                        0 => path_builder.move_to(point.x, point.y),
+                        _ => path_builder.line_to(point.x, point.y),
+                    }
+                }
+
+                path_builder.close();
+            }
+
+            if let Some(path) = path_builder.finish() {
+                pixmap.fill_path(&path, &paint, FillRule::Winding, transform, None);
+            }
+
+            continue;
+        }
+
+        let line_cap = match line.style.paint_cap() {
+            Cap::Butt => LineCap::Butt,
+            Cap::Round => LineCap::Round,
+            Cap::Square => LineCap::Square,
+        };
+
+        let dash = line
+            .style
+            .dash_array(line.stroke.width)
+            .and_then(|dash_array| StrokeDash::new(dash_array.to_vec(), 0.0));
+
+        let stroke = Stroke {
+            width: line.stroke.width,
+            line_cap,
+            line_join: LineJoin::Miter,
+            dash,
+            ..Default::default()
+        };
+
+        for (i, shape_path) in line.paths().iter().enumerate() {
+            let mut path_builder = PathBuilder::new();
+
+            let mut points = shape_path.points.iter();
+
+            if let Some(first) = points.next() {
+                path_builder.move_to(first.x, first.y);
+            }
+
+            for point in points {
+                path_builder.line_to(point.x, point.y);
+            }
+
+            if shape_path.closed {
+                path_builder.close();
+            }
+
+            let path = match path_builder.finish() {
+                Some(path) => path,
+                None => continue,
+            };
+
+            // the first path is the outline of filled shapes
+            if let (0, Some(fill)) = (i, line.fill()) {
+                let [r, g, b, a] = line.style.paint_color(fill).to_srgba_unmultiplied();
+
+                let mut fill_paint = Paint::default();
+                fill_paint.set_color_rgba8(r, g, b, a);
+                fill_paint.anti_alias = true;
+
+                pixmap.fill_path(&path, &fill_paint, FillRule::EvenOdd, transform, None);
+            }
+
+            pixmap.stroke_path(&path, &paint, &stroke, transform, None);
+        }
+    }
+
+    pixmap.encode_png().context("Failed to encode PNG")
+}
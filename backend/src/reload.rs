@@ -0,0 +1,100 @@
+use std::{
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant, SystemTime},
+};
+
+use log::{error, info, warn, LevelFilter};
+use shared::config::Config;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Callback that re-applies overrides (e.g. command-line flags) to a freshly loaded configuration
+pub type Overrides = Box<dyn Fn(&mut Config) + Send>;
+
+/// Polls the modification time of the configuration file and reloads it when it changes
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+    overrides: Overrides,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf, overrides: Overrides) -> Self {
+        Self {
+            modified: modified(&path),
+            path,
+            last_check: Instant::now(),
+            overrides,
+        }
+    }
+
+    /// Returns the new configuration if the file changed since the last call. Checks the file at most once per second.
+    pub fn poll(&mut self) -> Option<Config> {
+        if self.last_check.elapsed() < POLL_INTERVAL {
+            return None;
+        }
+
+        self.last_check = Instant::now();
+
+        let modified = modified(&self.path);
+
+        if modified == self.modified {
+            return None;
+        }
+
+        self.modified = modified;
+
+        let config = Config::load(&self.path).and_then(|mut config| {
+            (self.overrides)(&mut config);
+
+            config.validate()?;
+
+            Ok(config)
+        });
+
+        match config {
+            Ok(config) => Some(config),
+            Err(e) => {
+                error!("Failed to reload {}: {}", self.path.display(), e);
+                None
+            }
+        }
+    }
+}
+
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Copies the values that can change while the server is running from `new` into `current`.
+/// Changes to the listening address and the data directory are only reported, they need a restart.
+pub fn apply_reloadable(current: &mut Config, new: Config) {
+    if new.host.bind != current.host.bind || new.host.port != current.host.port {
+        warn!("Changes to host.bind and host.port require a restart");
+    }
+
+    if new.server.data_dir != current.server.data_dir {
+        warn!("Changes to server.data_dir require a restart");
+    }
+
+    if new.server.log_level != current.server.log_level {
+        match LevelFilter::from_str(&new.server.log_level) {
+            Ok(log_level) => {
+                log::set_max_level(log_level);
+                current.server.log_level = new.server.log_level;
+            }
+            Err(_) => warn!("Invalid log level: {}", new.server.log_level),
+        }
+    }
+
+    current.host.ip = new.host.ip;
+    current.website = new.website;
+    current.cors = new.cors;
+    current.limits = new.limits;
+
+    info!("Configuration reloaded");
+}
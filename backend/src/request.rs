@@ -0,0 +1,315 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result};
+
+use http::{Method, StatusCode};
+use shared::Peer;
+
+use crate::router::HttpError;
+
+/// Bytes of the request line and the headers together
+const MAX_HEADER_SIZE: usize = 16 * 1024;
+
+const MAX_HEADERS: usize = 100;
+
+/// String key-value pairs taken either from the query string or from the path parameters of a route
+#[derive(Debug, Default, Clone)]
+pub struct Params(pub HashMap<String, String>);
+
+impl Params {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(|value| value.as_str())
+    }
+
+    /// Parses the value under `key` into `T`. Returns `Ok(None)` if the key is missing.
+    pub fn parse<T>(&self, key: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        match self.get(key) {
+            Some(value) => {
+                let value = value
+                    .parse::<T>()
+                    .context(format!("Failed to parse parameter {}={}", key, value))?;
+
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn from_query(query: &str) -> Self {
+        let params = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => (decode_query(key), decode_query(value)),
+                None => (decode_query(pair), String::new()),
+            })
+            .collect();
+
+        Self(params)
+    }
+}
+
+#[derive(Debug)]
+pub struct Request {
+    pub method: Method,
+    pub path: String,
+    pub query: Params,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+    pub peer: Peer,
+}
+
+impl Request {
+    /// Reads a single HTTP/1.1 request from `stream`. Returns `Ok(None)` if the peer closed the connection without sending anything.
+    /// Bodies larger than `max_body_size` are rejected with `413 Payload Too Large`,
+    /// too many or too long headers with `431 Request Header Fields Too Large` and malformed requests with `400 Bad Request`.
+    pub fn read_from(
+        stream: &mut std::net::TcpStream,
+        peer: Peer,
+        max_body_size: usize,
+    ) -> Result<Option<Self>> {
+        let mut buf_reader = BufReader::new(stream);
+
+        let mut header_bytes_left = MAX_HEADER_SIZE;
+
+        let mut request_line = String::new();
+
+        if read_header_line(&mut buf_reader, &mut request_line, &mut header_bytes_left)? == 0 {
+            return Ok(None);
+        }
+
+        let request_line = request_line.trim_end();
+
+        if request_line.is_empty() {
+            return Ok(None);
+        }
+
+        let mut parts = request_line.split_whitespace();
+
+        let (method, target) = match (parts.next(), parts.next()) {
+            (Some(method), Some(target)) => (method, target),
+            _ => {
+                return Err(HttpError::bad_request(format!(
+                    "Malformed request line: {}",
+                    request_line
+                ))
+                .into())
+            }
+        };
+
+        let method = Method::from_bytes(method.as_bytes()).map_err(|_| {
+            HttpError::bad_request(format!("Invalid method in request line: {}", request_line))
+        })?;
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Params::from_query(query)),
+            None => (target, Params::default()),
+        };
+
+        let mut headers = HashMap::new();
+
+        loop {
+            let mut line = String::new();
+
+            if read_header_line(&mut buf_reader, &mut line, &mut header_bytes_left)? == 0 {
+                break;
+            }
+
+            let line = line.trim_end();
+
+            if line.is_empty() {
+                break;
+            }
+
+            if headers.len() == MAX_HEADERS {
+                return Err(HttpError::new(
+                    StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                    format!("More than {} headers", MAX_HEADERS),
+                )
+                .into());
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                headers.insert(key.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+
+        let content_length = match headers.get("content-length") {
+            Some(length) => length.parse::<usize>().map_err(|_| {
+                HttpError::bad_request(format!("Invalid content length: {}", length))
+            })?,
+            None => 0,
+        };
+
+        if content_length > max_body_size {
+            return Err(HttpError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!(
+                    "Request body of {} bytes exceeds the limit of {} bytes",
+                    content_length, max_body_size
+                ),
+            )
+            .into());
+        }
+
+        let mut body = vec![0; content_length];
+        buf_reader
+            .read_exact(&mut body)
+            .context("Failed to read request body")?;
+
+        Ok(Some(Self {
+            method,
+            path: path.to_string(),
+            query,
+            headers,
+            body,
+            peer,
+        }))
+    }
+
+    pub fn body_str(&self) -> Result<&str> {
+        std::str::from_utf8(&self.body).context("Request body is not valid UTF-8")
+    }
+}
+
+/// Reads a line of the head of a request, which may only take up `bytes_left` bytes. Returns the number of bytes read.
+fn read_header_line(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    bytes_left: &mut usize,
+) -> Result<usize> {
+    let read = reader.take(*bytes_left as u64).read_line(line)?;
+
+    *bytes_left -= read;
+
+    if *bytes_left == 0 && !line.ends_with('\n') {
+        return Err(HttpError::new(
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            format!("Request headers exceed {} bytes", MAX_HEADER_SIZE),
+        )
+        .into());
+    }
+
+    Ok(read)
+}
+
+/// Decodes a key or value of a query string, where `+` is a space
+pub(crate) fn decode_query(input: &str) -> String {
+    percent_decode(input, true)
+}
+
+/// Decodes a segment of a path, where `+` is just a plus sign
+pub(crate) fn decode_path(input: &str) -> String {
+    percent_decode(input, false)
+}
+
+fn percent_decode(input: &str, is_plus_space: bool) -> String {
+    let bytes = input.as_bytes();
+
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' if is_plus_space => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = |byte: u8| (byte as char).to_digit(16);
+
+                match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push((high * 16 + low) as u8);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_and_plus_signs_are_decoded() {
+        assert_eq!(decode_query("old%20town+map.png"), "old town map.png");
+        assert_eq!(decode_query("%C3%A4"), "ä");
+        assert_eq!(decode_query("%2f%2F"), "//");
+    }
+
+    #[test]
+    fn plus_signs_in_paths_are_kept() {
+        assert_eq!(decode_path("old%20town+map.png"), "old town+map.png");
+        assert_eq!(decode_path("a%2Bb"), "a+b");
+    }
+
+    #[test]
+    fn escapes_at_the_end_are_decoded() {
+        assert_eq!(decode_query("a%41"), "aA");
+        assert_eq!(decode_query("%41"), "A");
+    }
+
+    #[test]
+    fn truncated_and_invalid_escapes_are_kept() {
+        assert_eq!(decode_query("%"), "%");
+        assert_eq!(decode_query("a%4"), "a%4");
+        assert_eq!(decode_query("100%"), "100%");
+        assert_eq!(decode_query("%zz"), "%zz");
+    }
+
+    #[test]
+    fn header_lines_are_limited() {
+        let mut bytes_left = 20;
+
+        let mut reader = std::io::Cursor::new("Host: maps\r\nCookie: very long\r\n");
+
+        let mut line = String::new();
+
+        assert_eq!(
+            read_header_line(&mut reader, &mut line, &mut bytes_left).unwrap(),
+            12
+        );
+        assert_eq!(bytes_left, 8);
+
+        let e = read_header_line(&mut reader, &mut String::new(), &mut bytes_left).unwrap_err();
+
+        assert_eq!(
+            e.downcast_ref::<HttpError>().map(|e| e.status),
+            Some(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE)
+        );
+    }
+
+    #[test]
+    fn query_pairs_are_split_and_decoded() {
+        let params = Params::from_query("background=old%20town.png&download&&mode=merge");
+
+        assert_eq!(params.get("background"), Some("old town.png"));
+        assert_eq!(params.get("download"), Some(""));
+        assert_eq!(params.get("mode"), Some("merge"));
+        assert_eq!(params.0.len(), 3);
+    }
+
+    #[test]
+    fn params_are_parsed_on_request() {
+        let params = Params::from_query("size=12&scale=big");
+
+        assert_eq!(params.parse::<u32>("size").unwrap(), Some(12));
+        assert_eq!(params.parse::<u32>("missing").unwrap(), None);
+        assert!(params.parse::<f32>("scale").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+
+use http::{
+    header::{
+        self, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+        ACCESS_CONTROL_ALLOW_ORIGIN,
+    },
+    HeaderValue, StatusCode,
+};
+use log::warn;
+use serde::Serialize;
+use shared::config::{Config, Cors};
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: StatusCode,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+    /// Added to the default headers
+    pub headers: http::HeaderMap,
+}
+
+impl Response {
+    pub fn new(status: StatusCode, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            content_type,
+            body: body.into(),
+            headers: http::HeaderMap::new(),
+        }
+    }
+
+    pub fn with_header(mut self, key: header::HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(key, value);
+        self
+    }
+
+    /// Makes browsers save the body as `file_name` instead of showing it
+    pub fn as_attachment(self, file_name: &str) -> Self {
+        match HeaderValue::from_str(&format!("attachment; filename=\"{}\"", file_name)) {
+            Ok(value) => self.with_header(header::CONTENT_DISPOSITION, value),
+            Err(_) => self,
+        }
+    }
+
+    pub fn ok(content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self::new(StatusCode::OK, content_type, body)
+    }
+
+    /// 200 OK without a body. Used as the answer to POST requests.
+    pub fn empty() -> Self {
+        Self::ok("text/plain", Vec::new())
+    }
+
+    pub fn status(status: StatusCode) -> Self {
+        Self::new(status, "text/plain", Vec::new())
+    }
+
+    pub fn text(status: StatusCode, text: impl Into<String>) -> Self {
+        Self::new(status, "text/plain", text.into())
+    }
+
+    pub fn json<T: Serialize>(value: &T) -> Result<Self> {
+        let body = serde_json::to_vec(value).context("Failed to serialize response")?;
+
+        Ok(Self::ok("application/json", body))
+    }
+
+    pub fn file(path: impl AsRef<Path>, content_type: &'static str) -> Result<Self> {
+        let path = path.as_ref();
+
+        let body = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+
+        Ok(Self::ok(content_type, body))
+    }
+
+    /// Reads an html file and replaces every `[placeholder, value]` pair in it
+    pub fn html_template(path: impl AsRef<Path>, replace_content: &[[String; 2]]) -> Result<Self> {
+        let path = path.as_ref();
+
+        let mut string =
+            fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+
+        for replace in replace_content {
+            string = string.replace(&replace[0], &replace[1]);
+        }
+
+        Ok(Self::ok("text/html", string))
+    }
+
+    pub fn not_found(config: &Config) -> Self {
+        match fs::read(config.data_path("public/404.html")) {
+            Ok(body) => Self::new(StatusCode::NOT_FOUND, "text/html", body),
+            Err(_) => Self::status(StatusCode::NOT_FOUND),
+        }
+    }
+
+    pub fn into_bytes(self, cors: &Cors) -> Vec<u8> {
+        let mut headermap = prepare_headermap(self.content_type, self.body.len(), cors);
+        headermap.extend(self.headers);
+
+        let status_line = format!(
+            "HTTP/1.1 {} {}",
+            self.status.as_u16(),
+            self.status.canonical_reason().unwrap_or_default()
+        );
+
+        let head = format!("{}\r\n{}\r\n", status_line, {
+            headermap
+                .iter()
+                .fold(String::new(), |mut acc, (key, value)| {
+                    acc.push_str(&format!(
+                        "{}: {}\r\n",
+                        key,
+                        value.to_str().unwrap_or_default()
+                    ));
+                    acc
+                })
+        });
+
+        let mut bytes = head.into_bytes();
+        bytes.extend(self.body);
+
+        bytes
+    }
+}
+
+fn prepare_headermap(content_type: &'static str, length: usize, cors: &Cors) -> http::HeaderMap {
+    let mut headermap = http::HeaderMap::new();
+
+    match HeaderValue::from_str(&cors.allow_origin) {
+        Ok(allow_origin) => {
+            headermap.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        }
+        Err(_) => warn!("Invalid cors.allow_origin: {}", cors.allow_origin),
+    }
+
+    headermap.insert(
+        ACCESS_CONTROL_ALLOW_HEADERS,
+        HeaderValue::from_static("Origin, X-Requested-With, Content-Type, Accept"),
+    );
+    headermap.insert(
+        ACCESS_CONTROL_ALLOW_METHODS,
+        HeaderValue::from_static("GET, POST, OPTIONS"),
+    );
+    headermap.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    headermap.insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+    headermap
+}
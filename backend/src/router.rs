@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use http::{Method, StatusCode};
+use log::error;
+use shared::{config::Config, ClientID};
+use thiserror::Error;
+
+use crate::{
+    request::{decode_path, Params, Request},
+    response::Response,
+    state::State,
+};
+
+/// Everything a handler gets to see for a single request
+pub struct Context<'a> {
+    pub request: &'a Request,
+    pub params: Params,
+    pub state: &'a mut State,
+    pub config: &'a Config,
+}
+
+impl<'a> Context<'a> {
+    pub fn client_id(&self) -> Option<ClientID> {
+        let ip = self.request.peer.ip().ok()?;
+
+        self.state.clients.get(ip).map(|client| client.id)
+    }
+
+    /// Like [`Context::client_id`] but fails with `412 Precondition Failed` if the client never said hello
+    pub fn require_client_id(&self) -> Result<ClientID> {
+        match self.client_id() {
+            Some(client_id) => Ok(client_id),
+            None => Err(HttpError::new(
+                StatusCode::PRECONDITION_FAILED,
+                format!("Client ID not found for peer: {}", self.request.peer),
+            ))?,
+        }
+    }
+}
+
+pub type Handler = fn(&mut Context) -> Result<Response>;
+
+/// Error with an explicit status code. Any other error returned by a handler becomes a `500 Internal Server Error`.
+#[derive(Error, Debug)]
+#[error("{status}: {message}")]
+pub struct HttpError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl HttpError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+}
+
+/// A path like `/lines/:id`. Segments starting with `:` match any single segment and are captured as path parameters.
+#[derive(Debug, Clone)]
+pub struct PathPattern(Vec<Segment>);
+
+impl PathPattern {
+    pub fn new(pattern: &str) -> Self {
+        let segments = split_path(pattern)
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(name) => Segment::Param(name.to_string()),
+                None => Segment::Literal(segment.to_string()),
+            })
+            .collect();
+
+        Self(segments)
+    }
+
+    pub fn matches(&self, path: &str) -> Option<Params> {
+        let segments: Vec<&str> = split_path(path).collect();
+
+        if segments.len() != self.0.len() {
+            return None;
+        }
+
+        let mut params = HashMap::new();
+
+        for (pattern, segment) in self.0.iter().zip(segments) {
+            match pattern {
+                Segment::Literal(literal) => {
+                    if literal != segment {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    params.insert(name.clone(), decode_path(segment));
+                }
+            }
+        }
+
+        Some(Params(params))
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+struct Route {
+    method: Method,
+    pattern: PathPattern,
+    handler: Handler,
+}
+
+#[derive(Default)]
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn route(mut self, method: Method, pattern: &str, handler: Handler) -> Self {
+        self.routes.push(Route {
+            method,
+            pattern: PathPattern::new(pattern),
+            handler,
+        });
+
+        self
+    }
+
+    pub fn get(self, pattern: &str, handler: Handler) -> Self {
+        self.route(Method::GET, pattern, handler)
+    }
+
+    pub fn post(self, pattern: &str, handler: Handler) -> Self {
+        self.route(Method::POST, pattern, handler)
+    }
+
+    /// Dispatches `request` to the first matching route and turns handler errors into error responses
+    pub fn handle(&self, request: &Request, state: &mut State, config: &Config) -> Response {
+        let mut path_matched = false;
+
+        for route in self.routes.iter() {
+            let params = match route.pattern.matches(&request.path) {
+                Some(params) => params,
+                None => continue,
+            };
+
+            path_matched = true;
+
+            if route.method != request.method {
+                continue;
+            }
+
+            let mut context = Context {
+                request,
+                params,
+                state,
+                config,
+            };
+
+            return match (route.handler)(&mut context) {
+                Ok(response) => response,
+                Err(e) => {
+                    error!("{} {} failed: {:?}", request.method, request.path, e);
+
+                    match e.downcast_ref::<HttpError>() {
+                        Some(http_error) => Response::text(http_error.status, &http_error.message),
+                        None => Response::text(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+                    }
+                }
+            };
+        }
+
+        match path_matched {
+            true if request.method == Method::OPTIONS => Response::status(StatusCode::NO_CONTENT),
+            true => Response::status(StatusCode::METHOD_NOT_ALLOWED),
+            false => Response::not_found(config),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::Peer;
+
+    use super::*;
+
+    fn request(method: Method, path: &str) -> Request {
+        Request {
+            method,
+            path: path.to_string(),
+            query: Params::default(),
+            headers: HashMap::new(),
+            body: Vec::new(),
+            peer: Peer("127.0.0.1:4000".to_string()),
+        }
+    }
+
+    fn echo_id(context: &mut Context) -> Result<Response> {
+        match context.params.get("id") {
+            Some(id) => Ok(Response::ok("text/plain", id)),
+            None => Err(HttpError::bad_request("Missing id"))?,
+        }
+    }
+
+    fn fail(_context: &mut Context) -> Result<Response> {
+        Err(HttpError::new(StatusCode::CONFLICT, "Busy"))?
+    }
+
+    fn router() -> Router {
+        Router::new().get("/lines/:id", echo_id).post("/fail", fail)
+    }
+
+    fn handle(method: Method, path: &str) -> Response {
+        router().handle(
+            &request(method, path),
+            &mut State::new(),
+            &Config::default(),
+        )
+    }
+
+    #[test]
+    fn patterns_match_literals_and_capture_params() {
+        let pattern = PathPattern::new("/grids/:background/cells");
+
+        let params = pattern.matches("/grids/hills.png/cells").unwrap();
+
+        assert_eq!(params.get("background"), Some("hills.png"));
+
+        // empty segments are ignored, like a trailing slash
+        assert!(pattern.matches("//grids/hills.png/cells/").is_some());
+
+        assert!(pattern.matches("/grids/hills.png").is_none());
+        assert!(pattern.matches("/grid/hills.png/cells").is_none());
+    }
+
+    #[test]
+    fn path_params_are_percent_decoded() {
+        let params = PathPattern::new("/grids/:background")
+            .matches("/grids/old%20town+map.png")
+            .unwrap();
+
+        assert_eq!(params.get("background"), Some("old town+map.png"));
+    }
+
+    #[test]
+    fn requests_are_dispatched_to_the_matching_route() {
+        let response = handle(Method::GET, "/lines/42");
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body, b"42");
+    }
+
+    #[test]
+    fn unknown_paths_are_not_found_and_other_methods_not_allowed() {
+        assert_eq!(
+            handle(Method::GET, "/missing").status,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            handle(Method::POST, "/lines/42").status,
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+    }
+
+    #[test]
+    fn preflight_requests_of_known_paths_have_no_content() {
+        assert_eq!(
+            handle(Method::OPTIONS, "/lines/42").status,
+            StatusCode::NO_CONTENT
+        );
+        assert_eq!(
+            handle(Method::OPTIONS, "/missing").status,
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn http_errors_keep_their_status() {
+        let response = handle(Method::POST, "/fail");
+
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.body, b"Busy");
+    }
+}
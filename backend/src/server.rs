@@ -0,0 +1,237 @@
+use std::{
+    io::Write,
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+use log::{error, info, trace};
+use shared::{config::Config, Peer};
+
+use crate::{
+    handlers,
+    reload::{self, ConfigWatcher},
+    request::Request,
+    response::Response,
+    router::{HttpError, Router},
+    state::State,
+};
+
+/// Connections that send nothing for this long are dropped, they would block every other client
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The drawing server. Build it from a [`Config`], then either [`Server::run`] it on the current thread or [`Server::spawn`] it.
+pub struct Server {
+    listener: TcpListener,
+    router: Router,
+    state: State,
+    config: Config,
+    config_watcher: Option<ConfigWatcher>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl Server {
+    /// Binds to the address and port from `config`. Port `0` picks an ephemeral port, see [`Server::local_addr`].
+    pub fn bind(config: Config) -> Result<Self> {
+        let addr = format!("{}:{}", config.host.bind, config.host.port);
+
+        Self::bind_to(addr, config)
+    }
+
+    pub fn bind_to(addr: impl ToSocketAddrs, config: Config) -> Result<Self> {
+        let listener = TcpListener::bind(addr).context("Failed to bind listener")?;
+
+        Ok(Self {
+            listener,
+            router: handlers::routes(),
+            state: State::new(),
+            config,
+            config_watcher: None,
+            shutdown: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener
+            .local_addr()
+            .context("Failed to get local address")
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Reloads the configuration from `path` whenever the file changes and applies the values that are safe to change at runtime.
+    /// `overrides` runs on every reloaded configuration, so that e.g. command-line flags keep precedence over the file.
+    pub fn watch_config(
+        mut self,
+        path: impl Into<PathBuf>,
+        overrides: impl Fn(&mut Config) + Send + 'static,
+    ) -> Self {
+        self.config_watcher = Some(ConfigWatcher::new(path.into(), Box::new(overrides)));
+
+        self
+    }
+
+    pub fn shutdown_handle(&self) -> Result<ShutdownHandle> {
+        Ok(ShutdownHandle {
+            addr: self.local_addr()?,
+            shutdown: self.shutdown.clone(),
+        })
+    }
+
+    /// Serves connections until a [`ShutdownHandle`] asks it to stop
+    pub fn run(mut self) -> Result<()> {
+        info!("Listening on {}", self.local_addr()?);
+
+        for stream in self.listener.incoming() {
+            if self.shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    error!("Failed to accept connection: {:?}", e);
+                    continue;
+                }
+            };
+
+            if let Some(new_config) = self.config_watcher.as_mut().and_then(ConfigWatcher::poll) {
+                reload::apply_reloadable(&mut self.config, new_config);
+            }
+
+            match handle_connection(stream, &self.router, &mut self.state, &self.config)
+                .context("Failed to handle connection")
+            {
+                Ok(_) => (),
+                Err(e) => error!("Error: {:?}", e),
+            };
+        }
+
+        info!("Server stopped");
+
+        Ok(())
+    }
+
+    /// Runs the server on a background thread
+    pub fn spawn(self) -> Result<RunningServer> {
+        let addr = self.local_addr()?;
+        let shutdown_handle = self.shutdown_handle()?;
+
+        let thread = std::thread::Builder::new()
+            .name("server".to_string())
+            .spawn(move || self.run())
+            .context("Failed to spawn server thread")?;
+
+        Ok(RunningServer {
+            addr,
+            shutdown_handle,
+            thread,
+        })
+    }
+}
+
+/// Stops a running [`Server`] from another thread
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) -> Result<()> {
+        self.shutdown.store(true, Ordering::SeqCst);
+
+        // the accept loop only checks the flag when a connection comes in
+        let mut addr = self.addr;
+
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr {
+                SocketAddr::V4(_) => [127, 0, 0, 1].into(),
+                SocketAddr::V6(_) => std::net::Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+
+        TcpStream::connect(addr).context("Failed to wake up server")?;
+
+        Ok(())
+    }
+}
+
+pub struct RunningServer {
+    addr: SocketAddr,
+    shutdown_handle: ShutdownHandle,
+    thread: JoinHandle<Result<()>>,
+}
+
+impl RunningServer {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown_handle.clone()
+    }
+
+    /// Stops the server and waits for its thread to finish
+    pub fn shutdown(self) -> Result<()> {
+        self.shutdown_handle.shutdown()?;
+
+        match self.thread.join() {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::anyhow!("Server thread panicked")),
+        }
+    }
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    router: &Router,
+    state: &mut State,
+    config: &Config,
+) -> Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let peer = Peer(stream.peer_addr()?.to_string());
+
+    let request = match Request::read_from(&mut stream, peer, config.limits.max_body_size) {
+        Ok(Some(request)) => request,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            if let Some(http_error) = e.downcast_ref::<HttpError>() {
+                let response = Response::text(http_error.status, &http_error.message);
+
+                stream.write_all(&response.into_bytes(&config.cors))?;
+            }
+
+            return Err(e);
+        }
+    };
+
+    let client_id = match state.clients.get(request.peer.ip()?) {
+        Some(client) => client.id.to_string(),
+        None => "Unknown".to_string(),
+    };
+
+    trace!(
+        "Request by {} (ID: {}): {} {}",
+        request.peer.ip()?,
+        client_id,
+        request.method,
+        request.path
+    );
+
+    let response = router.handle(&request, state, config);
+
+    stream.write_all(&response.into_bytes(&config.cors))?;
+
+    Ok(())
+}
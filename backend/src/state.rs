@@ -0,0 +1,34 @@
+use std::collections::{HashMap, HashSet};
+
+use shared::{grid::Grid, ChangedLines, ClientID, Lines};
+
+pub struct Client {
+    pub id: ClientID,
+}
+
+pub struct State {
+    pub lines: Lines,
+    pub clients: HashMap<String, Client>,
+    pub clear_sync: Option<HashSet<ClientID>>,
+    pub changed_lines_sync: HashMap<ClientID, ChangedLines>,
+    /// Grids by the name of their map
+    pub grids: HashMap<String, Grid>,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self {
+            lines: Lines::default(),
+            clients: HashMap::new(),
+            clear_sync: None,
+            changed_lines_sync: HashMap::new(),
+            grids: HashMap::new(),
+        }
+    }
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
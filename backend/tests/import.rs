@@ -0,0 +1,180 @@
+use egui::{pos2, Pos2};
+use http::StatusCode;
+use server::{import::svg_to_lines, router::HttpError};
+use shared::{color::Color, Line};
+
+fn svg(body: &str) -> String {
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">{}</svg>"#,
+        body
+    )
+}
+
+fn points(line: &Line) -> Vec<Pos2> {
+    line.coordinates.iter().map(|p| p.0).collect()
+}
+
+fn assert_bad_request(svg: &str) {
+    let e = match svg_to_lines(svg) {
+        Ok(lines) => panic!("{} was imported as {:?}", svg, lines),
+        Err(e) => e,
+    };
+
+    assert_eq!(
+        e.downcast_ref::<HttpError>().map(|e| e.status),
+        Some(StatusCode::BAD_REQUEST),
+        "{}",
+        e
+    );
+}
+
+#[test]
+fn every_subpath_becomes_a_line() {
+    let lines = svg_to_lines(&svg(
+        r#"<path d="M 0 0 L 10 0 L 10 10 Z m 20 20 h 10" stroke="red" stroke-width="2"/>"#,
+    ))
+    .unwrap();
+
+    assert_eq!(lines.len(), 2);
+
+    assert_eq!(
+        points(&lines[0]),
+        [
+            pos2(0.0, 0.0),
+            pos2(10.0, 0.0),
+            pos2(10.0, 10.0),
+            pos2(0.0, 0.0)
+        ]
+    );
+    assert_eq!(points(&lines[1]), [pos2(20.0, 20.0), pos2(30.0, 20.0)]);
+
+    assert_eq!(lines[0].stroke.color, Color::from_rgb(255, 0, 0));
+    assert_eq!(lines[0].stroke.width, 2.0);
+}
+
+#[test]
+fn curves_are_flattened() {
+    let lines = svg_to_lines(&svg(r#"<path d="M 0 0 Q 50 50 100 0" stroke="black"/>"#)).unwrap();
+
+    let points = points(&lines[0]);
+
+    assert!(points.len() > 10);
+    assert_eq!(points.first(), Some(&pos2(0.0, 0.0)));
+    assert_eq!(points.last(), Some(&pos2(100.0, 0.0)));
+
+    // the curve reaches half way to its control point
+    assert!(points.iter().all(|p| (0.0..=25.0).contains(&p.y)));
+    assert!(points.iter().any(|p| p.y > 24.0));
+}
+
+#[test]
+fn transforms_of_groups_apply_to_coordinates_and_widths() {
+    let lines = svg_to_lines(&svg(r#"<g transform="translate(10 20) scale(2)">
+            <line x1="1" y1="1" x2="2px" y2="1" stroke="blue" stroke-width="3"/>
+        </g>"#))
+    .unwrap();
+
+    assert_eq!(points(&lines[0]), [pos2(12.0, 22.0), pos2(14.0, 22.0)]);
+    assert_eq!(lines[0].stroke.width, 6.0);
+}
+
+#[test]
+fn style_attributes_win_over_presentation_attributes() {
+    let lines = svg_to_lines(&svg(r#"<g opacity="0.5">
+            <polyline points="0,0 5,5" stroke="red" style="stroke: #00ff00; stroke-opacity: 0.5"/>
+        </g>"#))
+    .unwrap();
+
+    assert_eq!(
+        lines[0].stroke.color,
+        Color::from_rgba_unmultiplied(0, 255, 0, 64)
+    );
+}
+
+#[test]
+fn shapes_without_stroke_use_their_fill() {
+    let lines = svg_to_lines(&svg(
+        r#"<polygon points="0,0 10,0 10,10" fill="rgb(0, 0, 255)"/>
+        <rect x="0" y="0" width="4" height="2" fill="none"/>"#,
+    ))
+    .unwrap();
+
+    assert_eq!(lines.len(), 1);
+
+    assert_eq!(lines[0].stroke.color, Color::from_rgb(0, 0, 255));
+    assert_eq!(lines[0].stroke.width, 1.0);
+    assert_eq!(lines[0].coordinates.len(), 4);
+}
+
+#[test]
+fn unsupported_elements_are_skipped() {
+    let lines = svg_to_lines(&svg(r#"<circle cx="5" cy="5" r="5" stroke="red"/>
+        <text x="0" y="0">Label</text>
+        <defs><path d="M 0 0 L 1 1" stroke="red"/></defs>
+        <rect x="1" y="2" width="4" height="2" stroke="red"/>"#))
+    .unwrap();
+
+    assert_eq!(lines.len(), 1);
+
+    assert_eq!(
+        points(&lines[0]),
+        [
+            pos2(1.0, 2.0),
+            pos2(5.0, 2.0),
+            pos2(5.0, 4.0),
+            pos2(1.0, 4.0),
+            pos2(1.0, 2.0)
+        ]
+    );
+}
+
+#[test]
+fn malformed_documents_are_bad_requests() {
+    assert_bad_request("");
+    assert_bad_request("<svg><path d='M 0 0 L 1 1'></svg>");
+    assert_bad_request("<html><body/></html>");
+}
+
+#[test]
+fn invalid_path_data_is_a_bad_request() {
+    assert_bad_request(&svg(r#"<path d="M 0 0 L" stroke="red"/>"#));
+    assert_bad_request(&svg(r#"<path d="X 1 2" stroke="red"/>"#));
+}
+
+#[test]
+fn shapes_out_of_range_are_bad_requests() {
+    assert_bad_request(&svg(
+        r#"<line x1="0" y1="0" x2="1e300" y2="0" stroke="red"/>"#,
+    ));
+    assert_bad_request(&svg(
+        r#"<g transform="scale(1e400)"><line x1="0" y1="0" x2="1" y2="0" stroke="red"/></g>"#,
+    ));
+    assert_bad_request(&svg(
+        r#"<line x1="0" y1="0" x2="1" y2="0" stroke="red" stroke-width="1e300"/>"#,
+    ));
+}
+
+#[test]
+fn fills_use_their_own_opacity() {
+    let lines = svg_to_lines(&svg(
+        r#"<polygon points="0,0 10,0 10,10" fill="blue" fill-opacity="0.5" stroke-opacity="0.25"/>
+        <polyline points="0,0 5,5" stroke="red" fill="blue" fill-opacity="0.5"/>"#,
+    ))
+    .unwrap();
+
+    assert_eq!(
+        lines[0].stroke.color,
+        Color::from_rgba_unmultiplied(0, 0, 255, 128)
+    );
+    assert_eq!(lines[1].stroke.color, Color::from_rgb(255, 0, 0));
+}
+
+#[test]
+fn huge_curves_are_bad_requests() {
+    let curves = " C 0 900000 900000 900000 900000 0 C 900000 -900000 0 -900000 0 0".repeat(200);
+
+    assert_bad_request(&svg(&format!(
+        r#"<path d="M 0 0{}" stroke="red"/>"#,
+        curves
+    )));
+}
@@ -0,0 +1,59 @@
+//! The server on an ephemeral port, talked to over plain TCP.
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+};
+
+use server::Server;
+use shared::{config::Config, ClientID};
+
+/// Sends a request without a body and returns the status code and the body of the answer
+fn request(addr: SocketAddr, method: &str, path: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: 0\r\n\r\n",
+        method, path, addr
+    )
+    .unwrap();
+
+    // the server closes the connection after every answer
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+
+    (status, body.to_string())
+}
+
+#[test]
+fn answers_requests_until_it_is_shut_down() {
+    let server = Server::bind_to("127.0.0.1:0", Config::default()).unwrap();
+
+    let addr = server.local_addr().unwrap();
+
+    assert_ne!(addr.port(), 0);
+
+    let running = server.spawn().unwrap();
+
+    let (status, body) = request(addr, "GET", "/hello");
+
+    assert_eq!(status, 200);
+
+    assert!(serde_json::from_str::<ClientID>(&body).is_ok());
+
+    // the same peer keeps its id
+    assert_eq!(request(addr, "GET", "/hello").1, body);
+
+    assert_eq!(request(addr, "GET", "/missing").0, 404);
+    assert_eq!(request(addr, "POST", "/hello").0, 405);
+
+    // wakes the server through its shutdown handle and waits for the thread
+    running.shutdown().unwrap();
+
+    assert!(TcpStream::connect(addr).is_err());
+}
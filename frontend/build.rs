@@ -11,7 +11,12 @@ fn main() -> Result<(), Box<dyn Error>> {
     let dest_path = Path::new(&"../assets/").join("images.rs");
     let mut all_the_files = File::create(&dest_path)?;
 
+    // sidecar files with the scale of a map, named like the image
+    let calibrations_path = Path::new(&"../assets/").join("calibrations.rs");
+    let mut calibrations = File::create(&calibrations_path)?;
+
     writeln!(&mut all_the_files, r##"["##,)?;
+    writeln!(&mut calibrations, r##"["##,)?;
 
     for f in fs::read_dir(SOURCE_DIR)? {
         let f = f?;
@@ -20,6 +25,19 @@ fn main() -> Result<(), Box<dyn Error>> {
             continue;
         }
 
+        if f.path()
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            writeln!(
+                &mut calibrations,
+                r##"("{name}", include_str!(r#"{name}"#)),"##,
+                name = f.path().display(),
+            )?;
+
+            continue;
+        }
+
         writeln!(
             &mut all_the_files,
             r##"("{name}", include_bytes!(r#"{name}"#)),"##,
@@ -28,6 +46,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
     writeln!(&mut all_the_files, r##"]"##,)?;
+    writeln!(&mut calibrations, r##"]"##,)?;
 
     Ok(())
 }
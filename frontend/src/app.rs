@@ -2,19 +2,25 @@ use eframe::CreationContext;
 
 use egui::epaint::CircleShape;
 use egui::{
-    emath, pos2, Color32, ColorImage, Pos2, Rect, Sense, Shape, Stroke, TextureHandle, TextureId,
-    TextureOptions,
+    emath, pos2, Color32, ColorImage, FontId, Key, PointerButton, Pos2, Rect, Sense, Shape, Stroke,
+    TextureHandle, TextureId, TextureOptions, TouchDeviceId, TouchId, TouchPhase, Vec2,
 };
 use egui::{epaint, DragValue};
 
 use reqwest::Client as ReqwestClient;
 
-use shared::{ChangedLines, ClientID, Line, StrokeX};
-use shared::{Flag, Message};
+use shared::color::Color;
+use shared::grid::{Grid, GridKind, Snap};
+use shared::measure::{path_length, Calibration};
+use shared::shape::ShapeKind;
+use shared::smoothing::{Smoothing, StrokeFilter};
+use shared::style::{Cap, Pattern, StrokeStyle};
+use shared::text::{Text, DEFAULT_FONT_SIZE};
+use shared::{background_canvas_rect, ChangedLines, ClientID, Line, StrokeX};
+use shared::{snapshot::LoadMode, Flag, Message};
 use wasm_bindgen_futures::spawn_local;
 
-use std::collections::HashMap;
-use std::ops::Add;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
 
 use shared::Lines;
@@ -22,6 +28,14 @@ use shared::SPos2;
 
 use anyhow::Result;
 
+use crate::minimap;
+use crate::paint;
+use crate::preferences::{Palette, Preferences};
+use crate::ruler;
+use crate::selection::{Selection, SelectionMode};
+use crate::tools::{Button, InputBindings, Tool};
+use crate::view::View;
+
 use async_recursion::async_recursion;
 
 use lazy_static::lazy_static;
@@ -32,17 +46,79 @@ pub struct App {
     is_dark: bool,
     texture_handles: HashMap<TextureId, TextureHandle>,
     current_background_id: TextureId,
-    background_offset: Pos2,
-    zoom: f32,
+    view: View,
+    /// The map is fitted to the window in the next frame
+    is_fit_requested: bool,
+    /// Where the canvas was painted in the last frame, the zoom buttons zoom around its center
+    canvas_screen_rect: Rect,
+    /// Fingers on the screen by device and touch, pinches zoom around their center
+    touches: BTreeMap<(TouchDeviceId, TouchId), Pos2>,
+    show_minimap: bool,
     lines: Arc<Mutex<Lines>>,
     changed_lines: Arc<Mutex<ChangedLines>>,
     current_line_id: Option<usize>,
     get_lines_timer: Option<f64>,
     stroke: Stroke,
+    palette: Palette,
+    style: StrokeStyle,
+    /// Shape the next line is drawn as, the fill of polygons comes from `is_filled` and `fill_color`
+    shape: ShapeKind,
+    is_filled: bool,
+    fill_color: Color32,
+    /// Set by double click or enter, polygons are sent once they are finished
+    is_polygon_finished: bool,
+    /// Applied to freehand lines when they are finished, the tolerance is in screen points
+    stroke_filter: StrokeFilter,
+    /// Size of new text in canvas units
+    font_size: f32,
+    /// Text that is typed into, it replaces the line with the same id once the editor loses focus
+    editing_text: Option<(usize, Line)>,
+    /// Tool of the bindings without a tool of their own
+    tool: Tool,
+    /// Tool of the drag on the canvas, picked by the button that started it
+    gesture: Option<Tool>,
+    input_bindings: InputBindings,
+    is_input_settings_open: bool,
+    selection_mode: SelectionMode,
+    selection: Selection,
+    /// The selection has the current stroke, it is sent once the stroke widgets are let go
+    is_restroke_pending: bool,
+    eraser_mode: EraserMode,
+    /// Radius of the eraser in screen points, the same at every zoom
+    eraser_size: f32,
+    /// Pieces the eraser cut off during the drag, they are sent together with the erased lines once it is released
+    remainders: Lines,
+    /// Corners of the measured path in canvas coordinates
+    measurement: Vec<Pos2>,
+    /// Set by double click or enter, the next press starts a new measurement
+    is_measurement_finished: bool,
+    /// Scales of the maps from the files next to their images, by map name
+    map_calibrations: HashMap<String, Calibration>,
+    /// Scales the user measured, they replace the ones of the files
+    calibrations: HashMap<String, Calibration>,
+    /// Real length of the measured path, to calibrate the map with it
+    calibration_length: f32,
+    calibration_unit: String,
+    /// Grids of the maps by map name, as the backend has them
+    grids: Arc<Mutex<HashMap<String, Grid>>>,
+    grid_posts: Arc<Mutex<GridPosts>>,
+    /// What corners of shapes and measured points snap to while the grid is shown
+    snap: Snap,
+    /// Background of the last loaded snapshot, switched to in the next frame
+    loaded_background: Arc<Mutex<Option<String>>>,
 }
 
 const IMAGES: &[(&str, &[u8])] = &include!(concat!("../../assets/", "/images.rs"));
 
+/// `<map>.json` files next to the images
+const CALIBRATIONS: &[(&str, &str)] = &include!(concat!("../../assets/", "/calibrations.rs"));
+
+/// Factor of the zoom buttons
+const ZOOM_STEP: f32 = 1.25;
+
+/// Zoom factor per scrolled point
+const SCROLL_ZOOM_SPEED: f32 = 2e-3;
+
 impl App {
     pub fn new(cc: &CreationContext<'_>, host: String, client_id: String) -> Self {
         for (name, data) in IMAGES {
@@ -69,7 +145,38 @@ impl App {
             })
             .collect();
 
-        let first_background_id = texture_handles.iter().next().map(|(id, _)| *id).unwrap();
+        let map_calibrations = CALIBRATIONS
+            .iter()
+            .filter_map(|(file_path, data)| {
+                let file_name = file_path.split('/').last()?;
+
+                match serde_json::from_str::<Calibration>(data) {
+                    Ok(calibration) => Some((file_name.replace(".json", ""), calibration)),
+                    Err(e) => {
+                        println!("Failed to parse calibration {}: {}", file_path, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        let preferences: Preferences = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+
+        cc.egui_ctx.set_visuals(match preferences.is_dark {
+            true => egui::Visuals::dark(),
+            false => egui::Visuals::light(),
+        });
+
+        // the map of the last visit, if it still exists
+        let first_background_id = texture_handles
+            .iter()
+            .find(|(_, texture)| Some(texture.name()) == preferences.background)
+            .or_else(|| texture_handles.iter().next())
+            .map(|(id, _)| *id)
+            .unwrap();
 
         let client_id = ClientID(
             client_id
@@ -81,18 +188,264 @@ impl App {
         Self {
             client_id: client_id,
             host: host.to_string(),
-            is_dark: true,
+            is_dark: preferences.is_dark,
             texture_handles,
             current_background_id: first_background_id,
-            background_offset: Pos2::ZERO,
-            zoom: 0.0,
+            view: preferences.view.unwrap_or_default(),
+            is_fit_requested: preferences.view.is_none(),
+            canvas_screen_rect: Rect::ZERO,
+            touches: BTreeMap::new(),
+            show_minimap: preferences.show_minimap,
             lines: Default::default(),
             changed_lines: Arc::new(Mutex::new(ChangedLines::default())),
             current_line_id: None,
             get_lines_timer: None,
-            stroke: Stroke::new(5.0, Color32::RED),
+            stroke: preferences.stroke,
+            palette: preferences.palette,
+            style: StrokeStyle::default(),
+            shape: ShapeKind::Freehand,
+            is_filled: false,
+            fill_color: Color32::from_rgba_unmultiplied(255, 0, 0, 64),
+            is_polygon_finished: false,
+            stroke_filter: StrokeFilter::default(),
+            font_size: DEFAULT_FONT_SIZE,
+            editing_text: None,
+            tool: Tool::Draw,
+            gesture: None,
+            input_bindings: preferences.input_bindings,
+            is_input_settings_open: false,
+            selection_mode: SelectionMode::Rectangle,
+            selection: Selection::default(),
+            is_restroke_pending: false,
+            eraser_mode: EraserMode::Stroke,
+            eraser_size: 10.0,
+            remainders: Lines::default(),
+            measurement: Vec::new(),
+            is_measurement_finished: false,
+            map_calibrations,
+            calibrations: preferences.calibrations,
+            calibration_length: 1.0,
+            calibration_unit: "m".to_string(),
+            grids: Default::default(),
+            grid_posts: Default::default(),
+            snap: preferences.snap,
+            loaded_background: Default::default(),
+        }
+    }
+}
+
+impl App {
+    fn set_tool(&mut self, tool: Tool) {
+        if tool != Tool::Select {
+            self.selection.clear();
+        }
+
+        if tool != Tool::Measure {
+            self.measurement.clear();
+        }
+
+        // an open polygon is sent as it is
+        if tool != self.tool {
+            self.is_polygon_finished = true;
+        }
+
+        self.tool = tool;
+    }
+
+    fn current_shape(&self) -> ShapeKind {
+        match &self.shape {
+            ShapeKind::Polygon { .. } => ShapeKind::Polygon {
+                fill: self.is_filled.then_some(Color::from(self.fill_color)),
+            },
+            shape => shape.clone(),
+        }
+    }
+
+    /// The text under `canvas_pos`, or a new one that starts there
+    fn text_at(&self, lines: &Lines, canvas_pos: Pos2) -> (usize, Line) {
+        let existing = lines
+            .iter()
+            .find(|(_, line)| line.text().is_some() && line.hit_test(canvas_pos, 0.0));
+
+        match existing {
+            Some((line_id, line)) => (*line_id, line.clone()),
+            None => (
+                rand::random::<usize>(),
+                Line {
+                    stroke: StrokeX::from(self.stroke),
+                    style: self.style,
+                    shape: ShapeKind::Text(Text::new(SPos2(canvas_pos), self.font_size)),
+                    ..Line::new()
+                },
+            ),
+        }
+    }
+
+    /// Stores an edited text and sends it, empty text deletes the line
+    fn finish_text(&self, line_id: usize, mut line: Line) {
+        // the enter of the shortcut ends up in the content
+        if let ShapeKind::Text(text) = &mut line.shape {
+            text.content = text.content.trim_end().to_string();
+        }
+
+        let mut lines = self
+            .lines
+            .try_lock()
+            .expect(&format!("Failed to lock lines at line {}", line!()));
+
+        let is_new = !lines.contains_key(&line_id);
+
+        if line.is_drawable() {
+            lines.0.insert(line_id, line.clone());
+
+            self.send_lines(
+                Lines([(line_id, line)].into()),
+                (!is_new).then(|| ChangedLines([line_id].into_iter().collect())),
+            );
+        } else if !is_new {
+            lines.0.remove(&line_id);
+
+            self.changed_lines
+                .try_lock()
+                .expect(&format!("Failed to lock changed lines at line {}", line!()))
+                .0
+                .insert(line_id);
+        }
+    }
+
+    /// Gives the selected lines the current stroke, they are only changed here until they are sent
+    fn restroke_selection(&self) {
+        let mut lines = self
+            .lines
+            .try_lock()
+            .expect(&format!("Failed to lock lines at line {}", line!()));
+
+        for line_id in self.selection.line_ids.iter() {
+            if let Some(line) = lines.0.get_mut(line_id) {
+                line.stroke = StrokeX::from(self.stroke);
+            }
         }
     }
+
+    fn send_selection(&self) {
+        let lines = self
+            .lines
+            .try_lock()
+            .expect(&format!("Failed to lock lines at line {}", line!()));
+
+        self.send_lines(
+            self.selection.lines(&lines),
+            Some(self.selection.changed_lines()),
+        );
+    }
+
+    /// Removes the selected lines, they are deleted on the backend with the erased ones
+    fn delete_selection(&mut self) {
+        let mut lines = self
+            .lines
+            .try_lock()
+            .expect(&format!("Failed to lock lines at line {}", line!()));
+
+        let mut changed_lines = self
+            .changed_lines
+            .try_lock()
+            .expect(&format!("Failed to lock changed lines at line {}", line!()));
+
+        for line_id in self.selection.line_ids.drain() {
+            lines.0.remove(&line_id);
+            changed_lines.0.insert(line_id);
+        }
+    }
+
+    fn current_map_name(&self) -> Option<String> {
+        self.texture_handles
+            .get(&self.current_background_id)
+            .map(|texture| texture.name())
+    }
+
+    /// Scale of the current map, maps without one are measured in pixels
+    fn calibration(&self) -> Calibration {
+        self.current_map_name()
+            .and_then(|name| {
+                self.calibrations
+                    .get(&name)
+                    .or_else(|| self.map_calibrations.get(&name))
+                    .cloned()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Grid of the current map, maps without one have a hidden default grid
+    fn grid(&self) -> Grid {
+        let grids = self
+            .grids
+            .try_lock()
+            .expect(&format!("Failed to lock grids at line {}", line!()));
+
+        self.current_map_name()
+            .and_then(|name| grids.get(&name).copied())
+            .unwrap_or_default()
+    }
+
+    /// Replaces the grid of the current map for everyone
+    fn set_grid(&self, grid: Grid) {
+        let name = match self.current_map_name() {
+            Some(name) => name,
+            None => return,
+        };
+
+        self.grids
+            .try_lock()
+            .expect(&format!("Failed to lock grids at line {}", line!()))
+            .insert(name.clone(), grid);
+
+        self.grid_posts
+            .try_lock()
+            .expect(&format!("Failed to lock grid posts at line {}", line!()))
+            .sent += 1;
+
+        let grid_posts = self.grid_posts.clone();
+
+        let host = self.host.clone();
+
+        spawn_local(async move {
+            match send_grid(&host, &name, grid).await {
+                Ok(_) => (),
+                Err(e) => println!("Error sending grid: {:?}", e),
+            };
+
+            grid_posts
+                .try_lock()
+                .expect(&format!("Failed to lock grid posts at line {}", line!()))
+                .answered += 1;
+        });
+    }
+
+    /// Rect of the current map in canvas coordinates
+    fn canvas_rect(&self) -> Rect {
+        match self.texture_handles.get(&self.current_background_id) {
+            Some(texture) => background_canvas_rect(texture.size()),
+            None => Rect::NOTHING,
+        }
+    }
+
+    /// Sends `lines` to the backend, lines in `changed_lines` replace the ones other clients have
+    fn send_lines(&self, lines: Lines, changed_lines: Option<ChangedLines>) {
+        let host = self.host.clone();
+
+        spawn_local(async move {
+            let message = Message {
+                lines,
+                changed_lines,
+                flag: None,
+            };
+
+            match send_message(&host, message).await {
+                Ok(_) => (),
+                Err(e) => println!("Error: {:?} at Line: {}", e, line!()),
+            };
+        });
+    }
 }
 
 impl eframe::App for App {
@@ -107,23 +460,361 @@ impl eframe::App for App {
                     }
                 });
 
-                ui.horizontal(|ui| {
+                let is_stroke_changed = ui.horizontal(|ui| {
                     let epaint::Stroke { width, color } = &mut self.stroke;
 
-                    ui.add(DragValue::new(width).speed(0.1).clamp_range(0.0..=1000.0))
-                        .on_hover_text("Width");
-                    ui.color_edit_button_srgba(color);
+                    let width_response = ui
+                        .add(DragValue::new(width).speed(0.1).clamp_range(0.0..=1000.0))
+                        .on_hover_text("Width in pixels of the map");
+                    let is_width_changed = width_response.changed();
+                    let mut is_color_changed = ui.color_edit_button_srgba(color).changed();
                     ui.label("Stroke");
 
                     let (_id, stroke_rect) = ui.allocate_space(ui.spacing().interact_size);
-                    let left = stroke_rect.left_center();
-                    let right = stroke_rect.right_center();
-                    ui.painter().line_segment([left, right], (*width, *color));
+
+                    let preview = Line {
+                        coordinates: vec![
+                            SPos2(stroke_rect.left_center()),
+                            SPos2(stroke_rect.right_center()),
+                        ],
+                        stroke: StrokeX::new(*width, *color),
+                        style: self.style,
+                        shape: ShapeKind::Freehand,
+                        pressure: Vec::new(),
+                        flag: None,
+                    };
+
+                    let shapes = ui.fonts(|fonts| {
+                        paint::line_shapes(
+                            &preview,
+                            &emath::RectTransform::identity(stroke_rect),
+                            fonts,
+                        )
+                    });
+
+                    ui.painter().extend(shapes);
+
+                    is_color_changed |= self.palette.ui(ui, color);
+
+                    (
+                        is_width_changed || is_color_changed,
+                        width_response.has_focus(),
+                    )
                 });
 
+                let (is_stroke_changed, is_width_typed) = is_stroke_changed.inner;
+
+                // the stroke of the selection follows the stroke settings
+                if is_stroke_changed && !self.selection.is_empty() {
+                    self.restroke_selection();
+
+                    self.is_restroke_pending = true;
+                }
+
+                // not every frame while the width or the color is dragged or typed
+                if self.is_restroke_pending && !is_width_typed && !ui.ctx().is_using_pointer() {
+                    self.send_selection();
+
+                    self.is_restroke_pending = false;
+                }
+
+                ui.horizontal(|ui| {
+                    let style = &mut self.style;
+
+                    egui::ComboBox::from_id_source("pattern")
+                        .selected_text(format!("{:?}", style.pattern))
+                        .show_ui(ui, |ui| {
+                            for pattern in [Pattern::Solid, Pattern::Dashed, Pattern::Dotted] {
+                                ui.selectable_value(
+                                    &mut style.pattern,
+                                    pattern,
+                                    format!("{:?}", pattern),
+                                );
+                            }
+                        });
+
+                    egui::ComboBox::from_id_source("cap")
+                        .selected_text(format!("{:?} cap", style.cap))
+                        .show_ui(ui, |ui| {
+                            for cap in [Cap::Butt, Cap::Round, Cap::Square] {
+                                ui.selectable_value(&mut style.cap, cap, format!("{:?}", cap));
+                            }
+                        });
+
+                    ui.add(egui::Slider::new(&mut style.opacity, 0.0..=1.0).text("Opacity"));
+
+                    ui.toggle_value(&mut style.highlighter, "🖍")
+                        .on_hover_text("Highlighter, drawn beneath pen strokes");
+                });
+
+                ui.horizontal(|ui| {
+                    let filter = &mut self.stroke_filter;
+
+                    egui::ComboBox::from_label("Smoothing")
+                        .selected_text(smoothing_name(filter.smoothing))
+                        .show_ui(ui, |ui| {
+                            for smoothing in [
+                                Smoothing::None,
+                                Smoothing::MovingAverage,
+                                Smoothing::CatmullRom,
+                            ] {
+                                ui.selectable_value(
+                                    &mut filter.smoothing,
+                                    smoothing,
+                                    smoothing_name(smoothing),
+                                );
+                            }
+                        });
+
+                    if filter.smoothing == Smoothing::MovingAverage {
+                        ui.add(DragValue::new(&mut filter.window).clamp_range(1..=31))
+                            .on_hover_text("Points that are averaged");
+                    }
+
+                    ui.add(
+                        DragValue::new(&mut filter.tolerance)
+                            .speed(0.05)
+                            .clamp_range(0.0..=20.0)
+                            .suffix(" px"),
+                    )
+                    .on_hover_text(
+                        "Simplify, points closer than this to the finished stroke are dropped",
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    for tool in Tool::ALL {
+                        if ui
+                            .selectable_label(self.tool == tool, tool.name())
+                            .on_hover_text(format!("{:?}", tool.shortcut()))
+                            .clicked()
+                        {
+                            self.set_tool(tool);
+                        }
+                    }
+
+                    ui.toggle_value(&mut self.is_input_settings_open, "⚙")
+                        .on_hover_text("Mouse buttons of the tools");
+                });
+
+                ui.horizontal(|ui| {
+                    for shape in [
+                        ShapeKind::Freehand,
+                        ShapeKind::Segment,
+                        ShapeKind::Arrow,
+                        ShapeKind::Rectangle,
+                        ShapeKind::Ellipse,
+                        ShapeKind::Polygon { fill: None },
+                    ] {
+                        let is_selected = self.tool == Tool::Draw
+                            && std::mem::discriminant(&self.shape)
+                                == std::mem::discriminant(&shape);
+
+                        if ui.selectable_label(is_selected, shape.name()).clicked() {
+                            self.set_tool(Tool::Draw);
+                            self.shape = shape;
+
+                            // an open polygon is sent as it is
+                            self.is_polygon_finished = true;
+                        }
+                    }
+
+                    if let ShapeKind::Polygon { .. } = self.shape {
+                        ui.checkbox(&mut self.is_filled, "Fill");
+                        ui.color_edit_button_srgba(&mut self.fill_color);
+                        ui.label("Double click or Enter to finish");
+                    }
+                });
+
+                if self.tool == Tool::Text {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            DragValue::new(&mut self.font_size)
+                                .speed(0.5)
+                                .clamp_range(1.0..=1000.0),
+                        )
+                        .on_hover_text("Font size");
+                        ui.label("Click to place or edit text, Ctrl+Enter to finish");
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    let mut grid = self.grid();
+
+                    let mut is_grid_changed = ui
+                        .checkbox(&mut grid.is_visible, "Grid")
+                        .on_hover_text("Shown to everyone on this map")
+                        .changed();
+
+                    if grid.is_visible {
+                        for (kind, name) in [(GridKind::Square, "Square"), (GridKind::Hex, "Hex")] {
+                            is_grid_changed |=
+                                ui.selectable_value(&mut grid.kind, kind, name).changed();
+                        }
+
+                        is_grid_changed |= ui
+                            .add(
+                                DragValue::new(&mut grid.cell_size)
+                                    .speed(0.5)
+                                    .clamp_range(1.0..=10000.0)
+                                    .suffix(" px"),
+                            )
+                            .on_hover_text("Cell size in map pixels")
+                            .changed();
+
+                        for offset in grid.offset.iter_mut() {
+                            is_grid_changed |= ui
+                                .add(DragValue::new(offset).speed(0.5))
+                                .on_hover_text("Offset in map pixels")
+                                .changed();
+                        }
+
+                        let mut color: Color32 = grid.color.into();
+
+                        if ui.color_edit_button_srgba(&mut color).changed() {
+                            grid.color = Color::from(color);
+                            is_grid_changed = true;
+                        }
+
+                        ui.separator();
+
+                        ui.label("Snap");
+                        ui.selectable_value(&mut self.snap, Snap::None, "Off");
+                        ui.selectable_value(&mut self.snap, Snap::Intersections, "Corners");
+                        ui.selectable_value(&mut self.snap, Snap::Centers, "Centers");
+                    }
+
+                    if is_grid_changed {
+                        self.set_grid(grid);
+                    }
+                });
+
+                if self.tool == Tool::Measure {
+                    ui.horizontal(|ui| {
+                        let calibration = self.calibration();
+
+                        ui.label(format!(
+                            "1 {} = {:.2} px",
+                            calibration.unit, calibration.pixels_per_unit
+                        ));
+
+                        ui.separator();
+
+                        ui.add(
+                            DragValue::new(&mut self.calibration_length)
+                                .speed(0.1)
+                                .clamp_range(0.001..=f32::MAX),
+                        )
+                        .on_hover_text("Real length of the measured path");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.calibration_unit)
+                                .desired_width(40.0),
+                        )
+                        .on_hover_text("Unit");
+
+                        let map_name = self.current_map_name().unwrap_or_default();
+
+                        if ui
+                            .add_enabled(
+                                self.measurement.len() >= 2,
+                                egui::Button::new("Calibrate"),
+                            )
+                            .on_hover_text(
+                                "Measure a known distance first, it sets the scale of the map",
+                            )
+                            .clicked()
+                        {
+                            match Calibration::from_measurement(
+                                path_length(&self.measurement),
+                                self.calibration_length,
+                                &self.calibration_unit,
+                            ) {
+                                Some(calibration) => {
+                                    self.calibrations.insert(map_name.clone(), calibration);
+                                }
+                                None => println!("Measured path is too short to calibrate"),
+                            }
+                        }
+
+                        if self.calibrations.contains_key(&map_name)
+                            && ui
+                                .button("Reset")
+                                .on_hover_text("Use the scale the map comes with")
+                                .clicked()
+                        {
+                            self.calibrations.remove(&map_name);
+                        }
+
+                        ui.label("Click to add points, double click or Enter to finish");
+                    });
+                }
+
+                ui.horizontal(|ui| {
+                    if self.tool == Tool::Select {
+                        ui.selectable_value(
+                            &mut self.selection_mode,
+                            SelectionMode::Rectangle,
+                            "Rectangle",
+                        );
+                        ui.selectable_value(
+                            &mut self.selection_mode,
+                            SelectionMode::Lasso,
+                            "Lasso",
+                        );
+                    }
+
+                    if !self.selection.is_empty() {
+                        ui.label(format!("{} selected", self.selection.line_ids.len()));
+
+                        if ui
+                            .button("🗑")
+                            .on_hover_text("Delete the selection (Del)")
+                            .clicked()
+                        {
+                            self.delete_selection();
+                        }
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Eraser");
+                    ui.selectable_value(&mut self.eraser_mode, EraserMode::Stroke, "Strokes")
+                        .on_hover_text("Erases whole lines");
+                    ui.selectable_value(&mut self.eraser_mode, EraserMode::Partial, "Partial")
+                        .on_hover_text("Cuts lines apart");
+                    ui.add(
+                        DragValue::new(&mut self.eraser_size)
+                            .speed(0.5)
+                            .clamp_range(1.0..=200.0)
+                            .suffix(" px"),
+                    )
+                    .on_hover_text("Eraser radius");
+                });
+
+                if !ui.ctx().wants_keyboard_input() {
+                    let (is_delete_pressed, shortcut_tool) = ui.input(|i| {
+                        (
+                            i.key_pressed(Key::Delete),
+                            Tool::ALL.into_iter().find(|tool| {
+                                i.modifiers.is_none() && i.key_pressed(tool.shortcut())
+                            }),
+                        )
+                    });
+
+                    if is_delete_pressed {
+                        self.delete_selection();
+                    }
+
+                    if let Some(tool) = shortcut_tool {
+                        self.set_tool(tool);
+                    }
+                }
+
                 let host = self.host.clone();
 
                 if ui.button("Clear").clicked() {
+                    let host = host.clone();
+
                     spawn_local(async move {
                         match send_clear_lines_request(&host).await {
                             Ok(_) => (),
@@ -139,6 +830,37 @@ impl eframe::App for App {
                     lines.clear();
                 }
 
+                if ui.button("Import SVG").clicked() {
+                    let host = host.clone();
+
+                    spawn_local(async move {
+                        match import_svg(&host).await {
+                            Ok(_) => (),
+                            Err(e) => println!("Error importing svg: {:?}", e),
+                        };
+                    });
+                }
+
+                let loaded_background = self
+                    .loaded_background
+                    .try_lock()
+                    .expect(&format!(
+                        "Failed to lock loaded background at line {}",
+                        line!()
+                    ))
+                    .take();
+
+                if let Some(name) = loaded_background {
+                    match self
+                        .texture_handles
+                        .iter()
+                        .find(|(_, texture)| texture.name() == name)
+                    {
+                        Some((id, _)) => self.current_background_id = *id,
+                        None => println!("Snapshot uses unknown map {}", name),
+                    }
+                }
+
                 let current_map_name = match self.texture_handles.get(&self.current_background_id) {
                     Some(texture) => texture.name(),
                     None => {
@@ -147,6 +869,50 @@ impl eframe::App for App {
                     }
                 };
 
+                if ui
+                    .button("Save")
+                    .on_hover_text("Download the board")
+                    .clicked()
+                {
+                    let url = format!(
+                        "http://{}/snapshot?background={}&download=true",
+                        host,
+                        percent_encode(&current_map_name)
+                    );
+
+                    ui.ctx().output_mut(|output| {
+                        output.open_url = Some(egui::output::OpenUrl::new_tab(url));
+                    });
+                }
+
+                ui.menu_button("Load", |ui| {
+                    for (label, mode) in [
+                        ("Replace board", LoadMode::Replace),
+                        ("Merge into board", LoadMode::Merge),
+                    ] {
+                        if ui.button(label).clicked() {
+                            let host = host.clone();
+                            let loaded_background = self.loaded_background.clone();
+
+                            spawn_local(async move {
+                                match load_snapshot(&host, mode).await {
+                                    // a merged snapshot is drawn on the current map
+                                    Ok(_) if mode == LoadMode::Merge => (),
+                                    Ok(background) => {
+                                        *loaded_background.try_lock().expect(&format!(
+                                            "Failed to lock loaded background at line {}",
+                                            line!()
+                                        )) = background;
+                                    }
+                                    Err(e) => println!("Error loading snapshot: {:?}", e),
+                                };
+                            });
+
+                            ui.close_menu();
+                        }
+                    }
+                });
+
                 egui::ComboBox::from_label("Map")
                     .selected_text(format!("{}", current_map_name))
                     .show_ui(ui, |ui| {
@@ -159,46 +925,113 @@ impl eframe::App for App {
                         }
                     });
 
-                ui.label(format!("Zoom {:.2}", self.zoom));
+                let center = self.canvas_screen_rect.center();
+
+                ui.label(format!("Zoom {:.0}%", self.view.scale * 100.0));
                 ui.button("➖").clicked().then(|| {
-                    self.zoom = (self.zoom + 0.05).clamp(-2.0, 2.0);
+                    self.view.zoom_around(center, 1.0 / ZOOM_STEP);
                 });
                 ui.button("➕").clicked().then(|| {
-                    self.zoom = (self.zoom - 0.05).clamp(-2.0, 2.0);
+                    self.view.zoom_around(center, ZOOM_STEP);
                 });
+
+                if ui
+                    .button("Fit")
+                    .on_hover_text("Fit the map to the window")
+                    .clicked()
+                {
+                    self.is_fit_requested = true;
+                }
+
+                if ui
+                    .button("100%")
+                    .on_hover_text("One map pixel per screen pixel")
+                    .clicked()
+                {
+                    self.view.actual_size(center);
+                }
+
+                if ui
+                    .button("Reset")
+                    .on_hover_text("Actual size in the top left corner")
+                    .clicked()
+                {
+                    self.view.reset(self.canvas_screen_rect);
+                }
+
+                ui.toggle_value(&mut self.show_minimap, "🗺")
+                    .on_hover_text("Overview of the map");
             });
 
-            let original_background_size = self
-                .texture_handles
-                .get(&self.current_background_id)
-                .unwrap()
-                .size();
+            let canvas_size = ui.available_size_before_wrap();
 
-            let background_size = original_background_size
-                .iter()
-                .map(|x| (*x as f32))
-                .collect::<Vec<f32>>();
+            let (mut response, painter) = ui.allocate_painter(canvas_size, Sense::drag());
 
-            let offset_x = self.background_offset.x;
-            let offset_y = self.background_offset.y;
+            self.canvas_screen_rect = response.rect;
 
-            let shrink_x = background_size[0] * self.zoom;
-            let shrink_y = background_size[1] * self.zoom;
+            let canvas_rect = self.canvas_rect();
 
-            let shrink = emath::vec2(shrink_x, shrink_y);
+            if self.is_fit_requested {
+                self.view.fit(canvas_rect, response.rect);
+                self.is_fit_requested = false;
+            }
 
-            let background_rect = Rect::from_min_size(
-                Pos2 {
-                    x: offset_x,
-                    y: offset_y,
-                },
-                emath::vec2(background_size[0], background_size[1]),
-            )
-            .shrink2(shrink);
+            // two fingers pan and pinch instead of drawing
+            let (multi_touch, zoom_factor, hover_pos) = ui.ctx().input(|i| {
+                for event in i.events.iter() {
+                    if let egui::Event::Touch {
+                        device_id,
+                        id,
+                        phase,
+                        pos,
+                        ..
+                    } = event
+                    {
+                        match phase {
+                            TouchPhase::Start | TouchPhase::Move => {
+                                self.touches.insert((*device_id, *id), *pos);
+                            }
+                            TouchPhase::End | TouchPhase::Cancel => {
+                                self.touches.remove(&(*device_id, *id));
+                            }
+                        }
+                    }
+                }
 
-            let canvas_size = ui.available_size_before_wrap();
+                (
+                    i.multi_touch(),
+                    // pinches and ctrl + scroll, plain scrolling zooms as well
+                    i.zoom_delta() * (i.scroll_delta.y * SCROLL_ZOOM_SPEED).exp(),
+                    i.pointer.hover_pos(),
+                )
+            });
 
-            let (mut response, painter) = ui.allocate_painter(canvas_size, Sense::drag());
+            // the pointer follows only one of the fingers
+            let zoom_center = match multi_touch {
+                Some(_) if !self.touches.is_empty() => Some(
+                    (self
+                        .touches
+                        .values()
+                        .fold(Vec2::ZERO, |sum, pos| sum + pos.to_vec2())
+                        / self.touches.len() as f32)
+                        .to_pos2(),
+                ),
+                _ => hover_pos,
+            };
+
+            if let Some(touch) = multi_touch {
+                self.view.offset += touch.translation_delta;
+            }
+
+            if let Some(center) = zoom_center.filter(|center| response.rect.contains(*center)) {
+                self.view.zoom_around(center, zoom_factor);
+            }
+
+            let to_screen = self.view.to_screen(canvas_rect);
+
+            let from_screen = to_screen.inverse();
+
+            let background_rect = *to_screen.to();
 
             painter.image(
                 self.current_background_id,
@@ -207,21 +1040,71 @@ impl eframe::App for App {
                 Color32::WHITE,
             );
 
-            let from_screen = emath::RectTransform::from_to(background_rect, response.rect);
+            let grid = Some(self.grid()).filter(|grid| grid.is_visible);
+
+            if let Some(grid) = grid {
+                let visible_rect = from_screen
+                    .transform_rect(response.rect)
+                    .intersect(canvas_rect);
+
+                let stroke = Stroke::new(1.0, Color32::from(grid.color));
 
-            let to_screen = from_screen.inverse();
+                for path in grid.paths(visible_rect) {
+                    let points = path.into_iter().map(|p| to_screen * p).collect();
+
+                    painter.add(Shape::line(points, stroke));
+                }
+            }
+
+            let stroke_filter = StrokeFilter {
+                tolerance: self.stroke_filter.tolerance * from_screen.scale().x,
+                ..self.stroke_filter
+            };
 
             let mut lines = self
                 .lines
                 .try_lock()
                 .expect(&format!("Failed to lock lines at line {}", line!()));
 
-            if lines.is_empty() {
+            // an open polygon can be erased or deleted, drawing goes on with a new line then
+            if !self
+                .current_line_id
+                .is_some_and(|current_line_id| lines.contains_key(&current_line_id))
+            {
                 let current_line_id = rand::random::<usize>();
                 self.current_line_id = Some(current_line_id);
+                self.is_polygon_finished = false;
                 lines.0.insert(current_line_id, Line::new());
             }
 
+            // the first finger already used a tool when the second one lands, the gesture is reset below
+            if multi_touch.is_some() {
+                match self.gesture {
+                    Some(Tool::Draw) => {
+                        if let Some(current_line) = self
+                            .current_line_id
+                            .and_then(|line_id| lines.0.get_mut(&line_id))
+                        {
+                            match current_line.shape {
+                                // only the corner of this press
+                                ShapeKind::Polygon { .. } => {
+                                    current_line.coordinates.pop();
+                                }
+                                _ => {
+                                    current_line.coordinates.clear();
+                                    current_line.pressure.clear();
+                                }
+                            }
+                        }
+                    }
+                    Some(Tool::Select) => self.selection.cancel(&mut lines),
+                    Some(Tool::Measure) => {
+                        self.measurement.pop();
+                    }
+                    _ => (),
+                }
+            }
+
             let current_line = match lines.0.get_mut(match &self.current_line_id {
                 Some(current_line_id) => current_line_id,
                 None => {
@@ -236,54 +1119,143 @@ impl eframe::App for App {
                 }
             };
 
-            let which_mouse_button_down = response.ctx.input(|i| {
-                if i.pointer.primary_down() {
-                    MouseDown::Primary
-                } else if i.pointer.secondary_down() {
-                    MouseDown::Secondary
-                } else if i.pointer.middle_down() {
-                    MouseDown::Middle
-                } else {
-                    MouseDown::None
-                }
+            let (pressed_button, modifiers, is_any_down) = response.ctx.input(|i| {
+                (
+                    Button::ALL
+                        .into_iter()
+                        .find(|button| i.pointer.button_pressed((*button).into())),
+                    i.modifiers,
+                    i.pointer.any_down(),
+                )
             });
 
-            let mut cursor_icon = None;
+            // the tool stays the same until every button is released, even if the modifiers change
+            if multi_touch.is_some() || !is_any_down {
+                self.gesture = None;
+            } else if let (None, Some(button)) = (self.gesture, pressed_button) {
+                self.gesture = self.input_bindings.tool(button, modifiers, self.tool);
+            }
+
+            let is_pressed = pressed_button.is_some();
 
-            let scroll_delta = response.ctx.input(|i| i.scroll_delta);
+            let mut cursor_icon = None;
 
-            let scroll_delta_y = scroll_delta.y * 1e-3;
+            let is_polygon_finish_requested = response.ctx.input(|i| {
+                i.key_pressed(Key::Enter) || i.pointer.button_double_clicked(PointerButton::Primary)
+            });
 
-            if scroll_delta_y != 0.0 {
-                self.zoom = (self.zoom + scroll_delta_y).clamp(-2.0, 2.0);
-            }
+            // the last force of the frame, mice and pens without pressure have none
+            let force = response
+                .ctx
+                .input(|i| {
+                    i.events.iter().rev().find_map(|event| match event {
+                        egui::Event::Touch { force, .. } => *force,
+                        _ => None,
+                    })
+                })
+                .filter(|force| *force > 0.0);
 
             match response.interact_pointer_pos() {
                 Some(pointer_pos) => {
                     let canvas_pos = from_screen * pointer_pos;
 
-                    match which_mouse_button_down {
-                        MouseDown::Primary => {
-                            if current_line.coordinates.last() != Some(&SPos2(canvas_pos)) {
-                                current_line.coordinates.push(SPos2(canvas_pos));
-                                current_line.stroke = StrokeX(self.stroke.clone());
-                                response.mark_changed();
+                    // freehand strokes and the eraser follow the pointer exactly
+                    let snapped_pos = match grid {
+                        Some(grid) => grid.snap(canvas_pos, self.snap),
+                        None => canvas_pos,
+                    };
+
+                    match self.gesture {
+                        Some(Tool::Select) => {
+                            if is_pressed {
+                                self.selection.press(&lines, canvas_pos);
+                            } else {
+                                self.selection
+                                    .drag(&mut lines, canvas_pos, self.selection_mode);
+                            }
+                        }
+                        // text is typed into an editor, clicking elsewhere finishes it
+                        Some(Tool::Text) => {
+                            if is_pressed && self.editing_text.is_none() {
+                                self.editing_text = Some(self.text_at(&lines, canvas_pos));
                             }
                         }
-                        MouseDown::Secondary => {
-                            cursor_icon = Some(get_eraser_on_pointer(pointer_pos));
+                        Some(Tool::Draw) => {
+                            if current_line.coordinates.is_empty() {
+                                current_line.shape = self.current_shape();
+                            }
+
+                            let coordinates = &mut current_line.coordinates;
+
+                            match current_line.shape {
+                                ShapeKind::Freehand => {
+                                    if coordinates.last() != Some(&SPos2(canvas_pos)) {
+                                        coordinates.push(SPos2(canvas_pos));
+
+                                        // strokes get a pressure once a force is seen, mice draw at a uniform width
+                                        match force {
+                                            Some(force) => {
+                                                current_line
+                                                    .pressure
+                                                    .resize(coordinates.len() - 1, 1.0);
+                                                current_line.pressure.push(force);
+                                            }
+                                            None if !current_line.pressure.is_empty() => {
+                                                current_line.pressure.push(1.0)
+                                            }
+                                            None => (),
+                                        }
+                                    }
+                                }
+                                // every press adds a corner, dragging moves it
+                                ShapeKind::Polygon { .. } => {
+                                    match (is_pressed, coordinates.last_mut()) {
+                                        (false, Some(last)) => *last = SPos2(snapped_pos),
+                                        _ => coordinates.push(SPos2(snapped_pos)),
+                                    }
+                                }
+                                // from where the drag started to the pointer
+                                _ => {
+                                    if coordinates.is_empty() {
+                                        coordinates.push(SPos2(snapped_pos));
+                                    }
+
+                                    coordinates.truncate(1);
+                                    coordinates.push(SPos2(snapped_pos));
+                                }
+                            }
+
+                            current_line.stroke = StrokeX::from(self.stroke);
+                            current_line.style = self.style;
+                            response.mark_changed();
+                        }
+                        Some(Tool::Erase) => {
+                            cursor_icon =
+                                Some(get_eraser_on_pointer(pointer_pos, self.eraser_size));
 
                             let mut lines_to_remove: Vec<usize> = Vec::new();
+                            let mut remainders = Lines::default();
 
-                            const TOLERANCE: f32 = 10.0;
+                            // the stroke widths are added by the hit test, they are in canvas units as well
+                            let tolerance = self.eraser_size * from_screen.scale().x;
 
                             for (line_id, line) in lines.iter() {
-                                for coordinate in line.coordinates.iter() {
-                                    let distance = coordinate.0.distance(canvas_pos);
-
-                                    if distance < TOLERANCE {
-                                        lines_to_remove.push(*line_id);
-                                        break;
+                                match self.eraser_mode {
+                                    EraserMode::Stroke => {
+                                        if line.hit_test(canvas_pos, tolerance) {
+                                            lines_to_remove.push(*line_id);
+                                        }
+                                    }
+                                    EraserMode::Partial => {
+                                        if let Some(pieces) = line.erase(canvas_pos, tolerance) {
+                                            lines_to_remove.push(*line_id);
+
+                                            remainders.0.extend(
+                                                pieces
+                                                    .into_iter()
+                                                    .map(|piece| (rand::random::<usize>(), piece)),
+                                            );
+                                        }
                                     }
                                 }
                             }
@@ -295,27 +1267,87 @@ impl eframe::App for App {
 
                             for line_id in lines_to_remove {
                                 lines.0.remove(&line_id);
-                                changed_lines.0.insert(line_id);
+
+                                // pieces of this drag were never sent
+                                if self.remainders.0.remove(&line_id).is_none() {
+                                    changed_lines.0.insert(line_id);
+                                }
 
                                 response.mark_changed();
                             }
+
+                            lines.0.extend(remainders.0.clone());
+                            self.remainders.0.extend(remainders.0);
+                        }
+                        // every press adds a point, dragging moves it
+                        Some(Tool::Measure) => {
+                            if is_pressed {
+                                if self.is_measurement_finished {
+                                    self.measurement.clear();
+                                    self.is_measurement_finished = false;
+                                }
+
+                                self.measurement.push(snapped_pos);
+                            } else if let Some(last) = self.measurement.last_mut() {
+                                *last = snapped_pos;
+                            }
                         }
-                        MouseDown::Middle => {
+                        Some(Tool::Pan) => {
                             let drag_delta = response.drag_delta();
 
-                            self.background_offset = self.background_offset.add(drag_delta);
+                            self.view.offset += drag_delta;
                         }
-                        MouseDown::None => (),
+                        None => (),
                     }
 
                     drop(lines);
                 }
                 None => {
+                    if is_polygon_finish_requested {
+                        self.is_polygon_finished = true;
+
+                        // a double click leaves the same point twice
+                        self.measurement.dedup();
+                        self.is_measurement_finished = true;
+                    }
+
+                    let is_open_polygon = matches!(current_line.shape, ShapeKind::Polygon { .. })
+                        && !self.is_polygon_finished;
+
+                    // a double click leaves the same corner twice
+                    if !is_open_polygon {
+                        // a stroke without pressure is stored with a uniform width
+                        if current_line
+                            .pressure
+                            .iter()
+                            .all(|pressure| *pressure == 1.0)
+                        {
+                            current_line.pressure.clear();
+                        }
+
+                        current_line.coordinates.dedup();
+
+                        // only here, the server stores lines as they are sent
+                        current_line.smooth(&stroke_filter);
+                    }
+
                     let are_coordinates_empty = current_line.coordinates.is_empty();
 
                     drop(lines);
 
-                    if !are_coordinates_empty {
+                    let lines = self
+                        .lines
+                        .try_lock()
+                        .expect(&format!("Failed to lock lines at line {}", line!()));
+
+                    if let Some(moved_lines) = self.selection.release(&lines, from_screen.scale().x)
+                    {
+                        self.send_lines(self.selection.lines(&lines), Some(moved_lines));
+                    }
+
+                    drop(lines);
+
+                    if !are_coordinates_empty && !is_open_polygon {
                         let lines = {
                             let unlocked = self
                                 .lines
@@ -323,20 +1355,10 @@ impl eframe::App for App {
                                 .expect(&format!("Failed to lock lines at line {}", line!()));
                             unlocked.clone()
                         };
-                        let host = self.host.clone();
-
-                        spawn_local(async move {
-                            let message = Message {
-                                lines,
-                                changed_lines: None,
-                                flag: None,
-                            };
-
-                            match send_message(&host, message).await {
-                                Ok(_) => (),
-                                Err(e) => println!("Error: {:?} at Line: {}", e, line!()),
-                            };
-                        });
+
+                        self.send_lines(lines, None);
+
+                        self.palette.remember(self.stroke.color);
 
                         let mut lines = self
                             .lines
@@ -349,6 +1371,8 @@ impl eframe::App for App {
 
                         lines.0.insert(current_line_id, Line::new());
 
+                        self.is_polygon_finished = false;
+
                         response.mark_changed();
                     }
 
@@ -357,24 +1381,16 @@ impl eframe::App for App {
                         .try_lock()
                         .expect(&format!("Failed to lock changed lines at line {}", line!()));
 
-                    if !changed_lines.0.is_empty() {
-                        let changed_lines_cloned = changed_lines.clone();
+                    // the remainders of the eraser replace the lines it cut in a single update
+                    if !changed_lines.0.is_empty() || !self.remainders.is_empty() {
+                        let remainders = std::mem::take(&mut self.remainders);
 
-                        let host = self.host.clone();
-
-                        spawn_local(async move {
-                            match send_deleted_lines(&host, changed_lines_cloned).await {
-                                Ok(_) => (),
-                                Err(e) => println!("Error: {:?} at Line: {}", e, line!()),
-                            };
-                        });
-
-                        changed_lines.0.clear();
+                        self.send_lines(remainders, Some(std::mem::take(&mut *changed_lines)));
                     }
                 }
             }
 
-            let lines = {
+            let mut lines = {
                 let unlocked = self
                     .lines
                     .try_lock()
@@ -382,17 +1398,118 @@ impl eframe::App for App {
                 unlocked.clone()
             };
 
-            let shapes = lines
+            // the line that is drawn already looks like it will once it is finished
+            if let Some(current_line) = self
+                .current_line_id
+                .and_then(|line_id| lines.0.get_mut(&line_id))
+            {
+                current_line.smooth(&stroke_filter);
+            }
+
+            let editing_line_id = self.editing_text.as_ref().map(|(line_id, _)| *line_id);
+
+            // the text that is edited is shown by its editor
+            let mut drawable_lines: Vec<&Line> = lines
                 .iter()
-                .filter(|(_line_id, line)| line.coordinates.len() >= 2)
-                .map(|(_line_id, line)| {
-                    let points: Vec<Pos2> =
-                        line.coordinates.iter().map(|p| to_screen * **p).collect();
-                    egui::Shape::line(points, *line.stroke)
-                });
+                .filter(|(line_id, line)| line.is_drawable() && Some(**line_id) != editing_line_id)
+                .map(|(_, line)| line)
+                .collect();
+
+            // highlighter strokes go beneath pen strokes
+            drawable_lines.sort_by_key(|line| !line.style.highlighter);
+
+            let shapes: Vec<Shape> = ui.fonts(|fonts| {
+                drawable_lines
+                    .into_iter()
+                    .flat_map(|line| paint::line_shapes(line, &to_screen, fonts))
+                    .collect()
+            });
 
             painter.extend(shapes);
 
+            painter.extend(self.selection.shapes(
+                &lines,
+                &to_screen,
+                ui.visuals().selection.stroke,
+            ));
+
+            painter.extend(ruler::shapes(
+                ui,
+                &self.measurement,
+                &to_screen,
+                &self.calibration(),
+            ));
+
+            if self.show_minimap {
+                minimap::show(
+                    ui.ctx(),
+                    &mut self.view,
+                    response.rect,
+                    canvas_rect,
+                    self.current_background_id,
+                    &lines,
+                );
+            }
+
+            if let Some((line_id, mut line)) = self.editing_text.take() {
+                let color: Color32 = line.style.paint_color(line.stroke.color).into();
+
+                let (is_finished, is_cancelled) = match &mut line.shape {
+                    ShapeKind::Text(text) => {
+                        let font_id = FontId::proportional(text.font_size * to_screen.scale().y);
+
+                        // rows are only broken at new lines, like the exporters do
+                        let mut layouter = |ui: &egui::Ui, content: &str, _wrap_width: f32| {
+                            ui.fonts(|fonts| {
+                                fonts.layout_no_wrap(content.to_string(), font_id.clone(), color)
+                            })
+                        };
+
+                        let response = egui::Area::new("text editor")
+                            .fixed_pos(to_screen * text.anchor.0)
+                            .order(egui::Order::Foreground)
+                            .show(ui.ctx(), |ui| {
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut text.content)
+                                        .frame(false)
+                                        .margin(Vec2::ZERO)
+                                        .desired_rows(1)
+                                        .desired_width(font_id.size)
+                                        .layouter(&mut layouter),
+                                )
+                            })
+                            .inner;
+
+                        let (is_escape_pressed, is_finish_requested) = ui.input(|i| {
+                            (
+                                i.key_pressed(Key::Escape),
+                                i.modifiers.command && i.key_pressed(Key::Enter),
+                            )
+                        });
+
+                        let is_finished = response.lost_focus() || is_finish_requested;
+
+                        if !is_finished && !response.has_focus() {
+                            response.request_focus();
+                        }
+
+                        (is_finished, is_escape_pressed)
+                    }
+                    _ => (true, true),
+                };
+
+                // a cancelled edit is dropped, the line keeps its previous content
+                match (is_finished, is_cancelled) {
+                    (_, true) => (),
+                    (true, false) => self.finish_text(line_id, line),
+                    (false, false) => self.editing_text = Some((line_id, line)),
+                }
+            }
+
+            egui::Window::new("Mouse buttons")
+                .open(&mut self.is_input_settings_open)
+                .show(ui.ctx(), |ui| self.input_bindings.ui(ui));
+
             match cursor_icon {
                 Some(cursor_icon) => {
                     painter.add(cursor_icon);
@@ -451,11 +1568,78 @@ impl eframe::App for App {
                 }
             });
 
+            let grids = self.grids.clone();
+            let grid_posts = self.grid_posts.clone();
+
+            let host = self.host.clone();
+
+            let sent_grid_posts = self
+                .grid_posts
+                .try_lock()
+                .expect(&format!("Failed to lock grid posts at line {}", line!()))
+                .sent;
+
+            spawn_local(async move {
+                match get_grids(&host).await {
+                    Ok(other_grids) => {
+                        let grid_posts = grid_posts
+                            .try_lock()
+                            .expect(&format!("Failed to lock grid posts at line {}", line!()));
+
+                        // the backend may not have the grid that is being edited yet
+                        if grid_posts.sent == sent_grid_posts && grid_posts.is_answered() {
+                            *grids
+                                .try_lock()
+                                .expect(&format!("Failed to lock grids at line {}", line!())) =
+                                other_grids;
+                        }
+                    }
+                    Err(e) => println!("Error getting grids: {:?}", e),
+                };
+            });
+
             self.get_lines_timer = Some(seconds_since);
         }
 
         ctx.request_repaint_after(std::time::Duration::from_millis(500));
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let preferences = Preferences {
+            is_dark: self.is_dark,
+            stroke: self.stroke,
+            background: self.current_map_name(),
+            view: Some(self.view),
+            show_minimap: self.show_minimap,
+            calibrations: self.calibrations.clone(),
+            snap: self.snap,
+            palette: self.palette.clone(),
+            input_bindings: self.input_bindings.clone(),
+        };
+
+        eframe::set_value(storage, eframe::APP_KEY, &preferences);
+    }
+}
+
+fn smoothing_name(smoothing: Smoothing) -> &'static str {
+    match smoothing {
+        Smoothing::None => "None",
+        Smoothing::MovingAverage => "Moving average",
+        Smoothing::CatmullRom => "Catmull-Rom",
+    }
+}
+
+/// Escapes everything but unreserved characters, for map names in urls
+fn percent_encode(input: &str) -> String {
+    input
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
 }
 
 fn load_image_from_memory(image_data: &[u8]) -> Result<ColorImage, image::ImageError> {
@@ -500,20 +1684,38 @@ async fn get_message(host: &str) -> Result<Message> {
 }
 
 #[async_recursion(?Send)]
-async fn send_deleted_lines(host: &str, changed_lines: ChangedLines) -> Result<()> {
+async fn get_grids(host: &str) -> Result<HashMap<String, Grid>> {
     let client = ReqwestClient::new();
 
-    let body = serde_json::to_string(&changed_lines).unwrap() + "\r\n\r\n";
+    let response = match client
+        .get("http://".to_string() + host + "/grids")
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(_) => return Err(anyhow::anyhow!("Failed to get grids")),
+    };
+
+    let body = response.text().await?;
+
+    Ok(serde_json::from_str(&body)?)
+}
+
+#[async_recursion(?Send)]
+async fn send_grid(host: &str, background: &str, grid: Grid) -> Result<()> {
+    let client = ReqwestClient::new();
+
+    let body = serde_json::to_string(&grid)?;
 
     match client
-        .post("http://".to_string() + host + "/delete_lines")
+        .post("http://".to_string() + host + "/grids/" + &percent_encode(background))
         .header("Content-Type", "application/json")
         .body(body)
         .send()
         .await
     {
         Ok(_) => Ok(()),
-        Err(_) => Err(anyhow::anyhow!("Failed to send changed lines")),
+        Err(_) => Err(anyhow::anyhow!("Failed to send grid")),
     }
 }
 
@@ -535,11 +1737,98 @@ async fn send_clear_lines_request(host: &str) -> Result<()> {
     }
 }
 
-pub enum MouseDown {
-    None,
-    Primary,
-    Secondary,
-    Middle,
+/// Lets the user pick an SVG file and sends it to the backend, which adds its shapes as new lines.
+/// They show up with the next [`get_message`].
+#[async_recursion(?Send)]
+async fn import_svg(host: &str) -> Result<()> {
+    let file = match rfd::AsyncFileDialog::new()
+        .add_filter("SVG", &["svg"])
+        .pick_file()
+        .await
+    {
+        Some(file) => file,
+        None => return Ok(()),
+    };
+
+    let body = file.read().await;
+
+    let client = ReqwestClient::new();
+
+    match client
+        .post("http://".to_string() + host + "/import/svg")
+        .header("Content-Type", "image/svg+xml")
+        .body(body)
+        .send()
+        .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => Err(anyhow::anyhow!("Failed to send svg")),
+    }
+}
+
+/// Lets the user pick a snapshot file and sends it to the backend, which replaces the board with it or merges it in.
+/// Returns the background stored in the snapshot.
+#[async_recursion(?Send)]
+async fn load_snapshot(host: &str, mode: LoadMode) -> Result<Option<String>> {
+    let file = match rfd::AsyncFileDialog::new()
+        .add_filter("Board snapshot", &["json"])
+        .pick_file()
+        .await
+    {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+
+    let body = file.read().await;
+
+    let mode = match mode {
+        LoadMode::Replace => "replace",
+        LoadMode::Merge => "merge",
+    };
+
+    let client = ReqwestClient::new();
+
+    let response = match client
+        .post("http://".to_string() + host + "/snapshot?mode=" + mode)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(_) => return Err(anyhow::anyhow!("Failed to send snapshot")),
+    };
+
+    if !response.status().is_success() {
+        let message = response.text().await.unwrap_or_default();
+
+        return Err(anyhow::anyhow!("Snapshot was rejected: {}", message));
+    }
+
+    let body = response.text().await?;
+
+    Ok(serde_json::from_str::<Option<String>>(&body)?)
+}
+
+/// Grids this client sent, polls that overlap a post are dropped so that the edited grid does not jump back
+#[derive(Debug, Default)]
+struct GridPosts {
+    sent: usize,
+    answered: usize,
+}
+
+impl GridPosts {
+    fn is_answered(&self) -> bool {
+        self.answered == self.sent
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EraserMode {
+    /// Lines that are touched are erased completely
+    Stroke,
+    /// Only the part under the eraser is removed, the rest is split into new lines
+    Partial,
 }
 
 lazy_static! {
@@ -550,7 +1839,8 @@ lazy_static! {
 
         let fill = Color32::TRANSPARENT;
 
-        let stroke = Stroke::new(3.0, Color32::WHITE);
+        // thin, so that the ring marks the radius exactly
+        let stroke = Stroke::new(1.0, Color32::WHITE);
 
         CircleShape {
             center,
@@ -561,9 +1851,11 @@ lazy_static! {
     };
 }
 
-fn get_eraser_on_pointer(pointer_pos: Pos2) -> Shape {
+/// Outline of the area the eraser removes, `radius` is in screen points
+fn get_eraser_on_pointer(pointer_pos: Pos2, radius: f32) -> Shape {
     let mut circle_shape = CIRCLE.to_owned();
     circle_shape.center = pointer_pos;
+    circle_shape.radius = radius;
 
     egui::Shape::Circle(circle_shape)
 }
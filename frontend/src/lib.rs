@@ -1,2 +1,9 @@
 mod app;
+mod minimap;
+mod paint;
+mod preferences;
+mod ruler;
+mod selection;
+mod tools;
+mod view;
 mod web;
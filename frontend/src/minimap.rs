@@ -0,0 +1,80 @@
+use egui::{emath::RectTransform, Color32, Rect, Sense, Stroke, TextureId, Vec2};
+
+use shared::Lines;
+
+use crate::{paint, view::View};
+
+/// Length of the longer side of the minimap in screen points
+const MINIMAP_SIZE: f32 = 200.0;
+
+/// Distance to the corner of the canvas
+const MARGIN: f32 = 8.0;
+
+/// Overview of the whole map in the bottom right corner of `screen_rect`, the part that the view shows is outlined.
+/// Clicking or dragging on it centers the view there.
+pub fn show(
+    ctx: &egui::Context,
+    view: &mut View,
+    screen_rect: Rect,
+    canvas_rect: Rect,
+    background_id: TextureId,
+    lines: &Lines,
+) {
+    if !canvas_rect.is_positive() {
+        return;
+    }
+
+    let size = canvas_rect.size() * (MINIMAP_SIZE / canvas_rect.size().max_elem());
+
+    let pos = screen_rect.right_bottom() - size - Vec2::splat(MARGIN);
+
+    // an area of its own, so that the canvas beneath is not drawn on
+    egui::Area::new("minimap")
+        .fixed_pos(pos)
+        .order(egui::Order::Foreground)
+        .show(ctx, |ui| {
+            let (response, painter) = ui.allocate_painter(size, Sense::click_and_drag());
+
+            let to_minimap = RectTransform::from_to(canvas_rect, response.rect);
+
+            painter.image(
+                background_id,
+                response.rect,
+                Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                Color32::WHITE,
+            );
+
+            let shapes: Vec<egui::Shape> = ui.fonts(|fonts| {
+                lines
+                    .values()
+                    .filter(|line| line.is_drawable())
+                    .flat_map(|line| paint::line_shapes(line, &to_minimap, fonts))
+                    .collect()
+            });
+
+            painter.extend(shapes);
+
+            let viewport = view
+                .to_screen(canvas_rect)
+                .inverse()
+                .transform_rect(screen_rect);
+
+            painter.rect_stroke(
+                to_minimap.transform_rect(viewport),
+                0.0,
+                ui.visuals().selection.stroke,
+            );
+
+            painter.rect_stroke(
+                response.rect,
+                0.0,
+                Stroke::new(1.0, ui.visuals().window_stroke.color),
+            );
+
+            if let Some(pointer_pos) = response.interact_pointer_pos() {
+                let canvas_pos = to_minimap.inverse() * pointer_pos;
+
+                view.offset = screen_rect.center() - canvas_pos.to_vec2() * view.scale;
+            }
+        });
+}
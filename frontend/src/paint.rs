@@ -0,0 +1,140 @@
+use egui::{emath::RectTransform, text::Fonts, Align2, Color32, FontId, Mesh, Pos2, Shape, Stroke};
+
+use shared::{
+    shape::{stroke_sides, triangulate},
+    style::{dashes, extend_ends, Cap},
+    Line,
+};
+
+/// Shapes that paint `line` on screen with its shape and style, the same way the server exports it.
+/// Widths, dashes and text are in canvas units, they are scaled with the canvas.
+pub fn line_shapes(line: &Line, to_screen: &RectTransform, fonts: &Fonts) -> Vec<Shape> {
+    let scale = to_screen.scale().x;
+
+    let width = line.stroke.width * scale;
+    let color: Color32 = line.style.paint_color(line.stroke.color).into();
+
+    // egui lays out with the same font as the server
+    if let Some(text) = line.text() {
+        return vec![Shape::text(
+            fonts,
+            *to_screen * text.anchor.0,
+            Align2::LEFT_TOP,
+            &text.content,
+            FontId::proportional(text.font_size * to_screen.scale().y),
+            color,
+        )];
+    }
+
+    // pen pressure varies the width, which egui strokes can not, so the outline of every dash is filled
+    if let Some(parts) = line.pressure_parts() {
+        return parts
+            .into_iter()
+            .map(|(points, widths)| {
+                let points: Vec<Pos2> = points.into_iter().map(|p| *to_screen * p).collect();
+
+                let widths: Vec<f32> = widths.into_iter().map(|width| width * scale).collect();
+
+                pressure_shape(&points, &widths, color)
+            })
+            .collect();
+    }
+
+    let cap = line.style.paint_cap();
+
+    let mut shapes = Vec::new();
+
+    for (i, path) in line.paths().into_iter().enumerate() {
+        let mut points: Vec<Pos2> = path.points.iter().map(|p| *to_screen * *p).collect();
+
+        // the first path is the outline of filled shapes
+        if let (0, Some(fill)) = (i, line.fill()) {
+            shapes.push(fill_shape(&points, line.style.paint_color(fill).into()));
+        }
+
+        let dash_array = line.style.dash_array(width);
+
+        if path.closed {
+            if dash_array.is_none() {
+                shapes.push(Shape::closed_line(points, Stroke::new(width, color)));
+                continue;
+            }
+
+            if let Some(first) = points.first().copied() {
+                points.push(first);
+            }
+        }
+
+        let parts = match dash_array {
+            Some([dash, gap]) => dashes(&points, dash, gap),
+            None => vec![points],
+        };
+
+        shapes.extend(
+            parts
+                .into_iter()
+                .flat_map(|part| stroke_shapes(part, width, color, cap)),
+        );
+    }
+
+    shapes
+}
+
+/// A stroke that is `widths[i]` screen points wide at `points[i]`, as a strip of triangles
+fn pressure_shape(points: &[Pos2], widths: &[f32], color: Color32) -> Shape {
+    let mut mesh = Mesh::default();
+
+    let (left, right) = stroke_sides(points, widths);
+
+    for (left, right) in left.into_iter().zip(right) {
+        mesh.colored_vertex(left, color);
+        mesh.colored_vertex(right, color);
+    }
+
+    for i in (0..mesh.vertices.len().saturating_sub(2) as u32).step_by(2) {
+        mesh.add_triangle(i, i + 1, i + 2);
+        mesh.add_triangle(i + 1, i + 3, i + 2);
+    }
+
+    Shape::mesh(mesh)
+}
+
+/// egui only fills convex paths, so the polygon is triangulated into a mesh
+fn fill_shape(polygon: &[Pos2], color: Color32) -> Shape {
+    let mut mesh = Mesh::default();
+
+    for point in polygon {
+        mesh.colored_vertex(*point, color);
+    }
+
+    for [a, b, c] in triangulate(polygon) {
+        mesh.add_triangle(a as u32, b as u32, c as u32);
+    }
+
+    Shape::mesh(mesh)
+}
+
+/// egui paths have butt caps, the other caps are added here
+fn stroke_shapes(mut points: Vec<Pos2>, width: f32, color: Color32, cap: Cap) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+
+    match cap {
+        Cap::Butt => (),
+        Cap::Square => extend_ends(&mut points, width / 2.0),
+        Cap::Round => {
+            if let (Some(first), Some(last)) = (points.first(), points.last()) {
+                shapes.push(Shape::circle_filled(*first, width / 2.0, color));
+
+                if first != last {
+                    shapes.push(Shape::circle_filled(*last, width / 2.0, color));
+                }
+            }
+        }
+    }
+
+    if points.len() >= 2 {
+        shapes.push(Shape::line(points, Stroke::new(width, color)));
+    }
+
+    shapes
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use egui::{Color32, Stroke, Ui};
+use serde::{Deserialize, Serialize};
+use shared::{grid::Snap, measure::Calibration};
+
+use crate::tools::InputBindings;
+use crate::view::View;
+
+/// Colors the palette remembers
+const RECENT_COLORS: usize = 10;
+
+/// Settings that are restored on the next visit, stored by eframe in the browser
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Preferences {
+    pub is_dark: bool,
+    pub stroke: Stroke,
+    /// Name of the map, textures get new ids on every load
+    pub background: Option<String>,
+    /// `None` until the view was saved once, the map is fitted to the window then
+    pub view: Option<View>,
+    pub show_minimap: bool,
+    /// Scales the user measured, by map name
+    pub calibrations: HashMap<String, Calibration>,
+    pub snap: Snap,
+    pub palette: Palette,
+    pub input_bindings: InputBindings,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            is_dark: true,
+            stroke: Stroke::new(5.0, Color32::RED),
+            background: None,
+            view: None,
+            show_minimap: false,
+            calibrations: HashMap::new(),
+            snap: Snap::None,
+            palette: Palette::default(),
+            input_bindings: InputBindings::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ColorPreset {
+    pub name: String,
+    pub color: Color32,
+}
+
+/// Recently drawn colors and the presets of the user
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Palette {
+    /// Most recent first
+    pub recent: Vec<Color32>,
+    pub presets: Vec<ColorPreset>,
+    /// Name of the next preset while it is typed
+    #[serde(skip)]
+    new_preset_name: String,
+}
+
+impl Palette {
+    /// Moves `color` to the front of the recent colors
+    pub fn remember(&mut self, color: Color32) {
+        self.recent.retain(|recent| *recent != color);
+        self.recent.insert(0, color);
+        self.recent.truncate(RECENT_COLORS);
+    }
+
+    /// Swatches of the recent colors and a menu of the presets. Returns whether `color` was picked.
+    pub fn ui(&mut self, ui: &mut Ui, color: &mut Color32) -> bool {
+        let mut is_picked = false;
+
+        for recent in self.recent.iter() {
+            if swatch(ui, *recent).clicked() {
+                *color = *recent;
+                is_picked = true;
+            }
+        }
+
+        ui.menu_button("Presets", |ui| {
+            let mut removed = None;
+
+            for (i, preset) in self.presets.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    if swatch(ui, preset.color).clicked() {
+                        *color = preset.color;
+                        is_picked = true;
+                    }
+
+                    ui.text_edit_singleline(&mut preset.name);
+
+                    if ui
+                        .button("⟲")
+                        .on_hover_text("Replace with the current color")
+                        .clicked()
+                    {
+                        preset.color = *color;
+                    }
+
+                    if ui.button("🗑").clicked() {
+                        removed = Some(i);
+                    }
+                });
+            }
+
+            if let Some(i) = removed {
+                self.presets.remove(i);
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.new_preset_name)
+                    .on_hover_text("Name of the preset");
+
+                let name = self.new_preset_name.trim().to_string();
+
+                if ui
+                    .add_enabled(!name.is_empty(), egui::Button::new("Add"))
+                    .on_hover_text("Save the current color")
+                    .clicked()
+                {
+                    self.presets.push(ColorPreset {
+                        name,
+                        color: *color,
+                    });
+                    self.new_preset_name.clear();
+                }
+            });
+        });
+
+        is_picked
+    }
+}
+
+fn swatch(ui: &mut Ui, color: Color32) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(
+        egui::Vec2::splat(ui.spacing().interact_size.y),
+        egui::Sense::click(),
+    );
+
+    ui.painter().rect_filled(rect, 2.0, color);
+
+    response
+}
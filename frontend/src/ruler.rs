@@ -0,0 +1,64 @@
+use egui::{emath::RectTransform, FontId, Pos2, Rect, Shape, Stroke, Ui, Vec2};
+
+use shared::measure::{path_length, polygon_area, Calibration};
+
+/// Radius of the corners of the measured path in screen points
+const CORNER_RADIUS: f32 = 3.0;
+
+/// The measured path with its length, and the area it encloses once it has three corners
+pub fn shapes(
+    ui: &Ui,
+    points: &[Pos2],
+    to_screen: &RectTransform,
+    calibration: &Calibration,
+) -> Vec<Shape> {
+    let last = match points.last() {
+        Some(last) => *to_screen * *last,
+        None => return Vec::new(),
+    };
+
+    let stroke = ui.visuals().selection.stroke;
+
+    let screen_points: Vec<Pos2> = points.iter().map(|p| *to_screen * *p).collect();
+
+    let mut shapes = Shape::dashed_line(&screen_points, stroke, 6.0, 4.0);
+
+    shapes.extend(
+        screen_points
+            .iter()
+            .map(|point| Shape::circle_filled(*point, CORNER_RADIUS, stroke.color)),
+    );
+
+    let mut label = calibration.format_length(path_length(points));
+
+    if points.len() >= 3 {
+        label += &format!("\nArea {}", calibration.format_area(polygon_area(points)));
+
+        // the closing side, which only counts for the area
+        shapes.extend(Shape::dashed_line(
+            &[last, screen_points[0]],
+            Stroke::new(stroke.width, stroke.color.gamma_multiply(0.5)),
+            6.0,
+            4.0,
+        ));
+    }
+
+    let galley = ui.fonts(|fonts| {
+        fonts.layout_no_wrap(
+            label,
+            FontId::proportional(14.0),
+            ui.visuals().strong_text_color(),
+        )
+    });
+
+    let rect = Rect::from_min_size(last + Vec2::splat(8.0), galley.size()).expand(4.0);
+
+    shapes.push(Shape::rect_filled(
+        rect,
+        2.0,
+        ui.visuals().extreme_bg_color.gamma_multiply(0.8),
+    ));
+    shapes.push(Shape::galley(rect.shrink(4.0).min, galley));
+
+    shapes
+}
@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+
+use egui::{emath::RectTransform, pos2, Pos2, Rect, Shape, Stroke};
+
+use shared::{ChangedLines, Lines};
+
+/// Gestures that stay this close to where they started, in screen points, are clicks
+const CLICK_DISTANCE: f32 = 3.0;
+
+/// Screen distance between the selected lines and their highlighted bounding box
+const HIGHLIGHT_MARGIN: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectionMode {
+    Rectangle,
+    Lasso,
+}
+
+/// Selected lines and the gesture that is changing them
+#[derive(Debug, Default)]
+pub struct Selection {
+    pub line_ids: HashSet<usize>,
+    /// Outline of the area that is being selected in canvas coordinates, starting where the drag started
+    area: Vec<Pos2>,
+    /// Where moving the selected lines started
+    drag_start: Option<Pos2>,
+    /// Where the selected lines were moved to in the last frame
+    drag_pos: Option<Pos2>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.line_ids.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Bounding box of all selected lines
+    pub fn bounding_rect(&self, lines: &Lines) -> Option<Rect> {
+        self.line_ids
+            .iter()
+            .filter_map(|line_id| lines.get(line_id))
+            .filter(|line| line.is_drawable())
+            .map(|line| line.bounding_rect())
+            .reduce(|a, b| a.union(b))
+    }
+
+    /// Pressing on the selection starts moving it, anywhere else starts a new selection
+    pub fn press(&mut self, lines: &Lines, pos: Pos2) {
+        if self
+            .bounding_rect(lines)
+            .is_some_and(|rect| rect.contains(pos))
+        {
+            self.drag_start = Some(pos);
+            self.drag_pos = Some(pos);
+        } else {
+            self.line_ids.clear();
+            self.area = vec![pos];
+        }
+    }
+
+    pub fn drag(&mut self, lines: &mut Lines, pos: Pos2, mode: SelectionMode) {
+        if let Some(drag_pos) = self.drag_pos {
+            for line_id in self.line_ids.iter() {
+                if let Some(line) = lines.0.get_mut(line_id) {
+                    line.translate(pos - drag_pos);
+                }
+            }
+
+            self.drag_pos = Some(pos);
+
+            return;
+        }
+
+        let start = match self.area.first() {
+            Some(start) => *start,
+            None => return,
+        };
+
+        match mode {
+            SelectionMode::Rectangle => {
+                self.area = vec![start, pos2(pos.x, start.y), pos, pos2(start.x, pos.y)];
+            }
+            SelectionMode::Lasso => {
+                if self.area.last() != Some(&pos) {
+                    self.area.push(pos);
+                }
+            }
+        }
+    }
+
+    /// Drops the gesture, moved lines go back to where they were
+    pub fn cancel(&mut self, lines: &mut Lines) {
+        if let (Some(drag_start), Some(drag_pos)) = (self.drag_start.take(), self.drag_pos.take()) {
+            for line_id in self.line_ids.iter() {
+                if let Some(line) = lines.0.get_mut(line_id) {
+                    line.translate(drag_start - drag_pos);
+                }
+            }
+        }
+
+        self.area.clear();
+    }
+
+    /// Ends the gesture. Returns the lines that were moved, they have to be sent.
+    /// `scale` is the size of a screen point in canvas units.
+    pub fn release(&mut self, lines: &Lines, scale: f32) -> Option<ChangedLines> {
+        if self.drag_pos.take().is_some() {
+            self.drag_start = None;
+
+            return Some(self.changed_lines());
+        }
+
+        let area = std::mem::take(&mut self.area);
+
+        let start = *area.first()?;
+
+        let click_distance = CLICK_DISTANCE * scale;
+
+        self.line_ids = if area.iter().all(|pos| pos.distance(start) < click_distance) {
+            // a click selects a single line
+            lines
+                .iter()
+                .find(|(_, line)| line.is_drawable() && line.hit_test(start, click_distance))
+                .map(|(line_id, _)| *line_id)
+                .into_iter()
+                .collect()
+        } else {
+            lines
+                .iter()
+                .filter(|(_, line)| line.is_inside(&area))
+                .map(|(line_id, _)| *line_id)
+                .collect()
+        };
+
+        None
+    }
+
+    /// The selected lines, to send them after they were changed
+    pub fn lines(&self, lines: &Lines) -> Lines {
+        Lines(
+            self.line_ids
+                .iter()
+                .filter_map(|line_id| Some((*line_id, lines.get(line_id)?.clone())))
+                .collect(),
+        )
+    }
+
+    pub fn changed_lines(&self) -> ChangedLines {
+        ChangedLines(self.line_ids.iter().copied().collect())
+    }
+
+    /// Outline of the area that is being selected and the bounding box of the selection
+    pub fn shapes(&self, lines: &Lines, to_screen: &RectTransform, stroke: Stroke) -> Vec<Shape> {
+        let mut shapes = Vec::new();
+
+        if self.area.len() >= 2 {
+            let mut points: Vec<Pos2> = self.area.iter().map(|p| *to_screen * *p).collect();
+            points.push(points[0]);
+
+            shapes.extend(Shape::dashed_line(&points, stroke, 4.0, 4.0));
+        }
+
+        if let Some(rect) = self.bounding_rect(lines) {
+            shapes.push(Shape::rect_stroke(
+                to_screen.transform_rect(rect).expand(HIGHLIGHT_MARGIN),
+                0.0,
+                stroke,
+            ));
+        }
+
+        shapes
+    }
+}
@@ -0,0 +1,227 @@
+use egui::{Key, Modifiers, PointerButton, Ui};
+use serde::{Deserialize, Serialize};
+
+/// What a drag on the canvas does
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    /// Draws the shape picked in the toolbar
+    Draw,
+    Text,
+    Select,
+    Erase,
+    Pan,
+    /// Measures lengths and areas on the map
+    Measure,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 6] = [
+        Tool::Draw,
+        Tool::Text,
+        Tool::Select,
+        Tool::Erase,
+        Tool::Pan,
+        Tool::Measure,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Draw => "Draw",
+            Tool::Text => "Text",
+            Tool::Select => "Select",
+            Tool::Erase => "Erase",
+            Tool::Pan => "Pan",
+            Tool::Measure => "Measure",
+        }
+    }
+
+    /// Picks the tool while the canvas has the keyboard
+    pub fn shortcut(&self) -> Key {
+        match self {
+            Tool::Draw => Key::D,
+            Tool::Text => Key::T,
+            Tool::Select => Key::S,
+            Tool::Erase => Key::E,
+            Tool::Pan => Key::H,
+            Tool::Measure => Key::M,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    Primary,
+    Secondary,
+    Middle,
+}
+
+impl Button {
+    pub const ALL: [Button; 3] = [Button::Primary, Button::Secondary, Button::Middle];
+}
+
+impl From<Button> for PointerButton {
+    fn from(button: Button) -> Self {
+        match button {
+            Button::Primary => PointerButton::Primary,
+            Button::Secondary => PointerButton::Secondary,
+            Button::Middle => PointerButton::Middle,
+        }
+    }
+}
+
+/// Key that has to be held for a [`Binding`], `None` matches any
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    None,
+    Shift,
+    /// Ctrl, or Cmd on Mac
+    Command,
+    Alt,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 4] = [
+        Modifier::None,
+        Modifier::Shift,
+        Modifier::Command,
+        Modifier::Alt,
+    ];
+
+    fn is_held(&self, modifiers: Modifiers) -> bool {
+        match self {
+            Modifier::None => true,
+            Modifier::Shift => modifiers.shift,
+            Modifier::Command => modifiers.command,
+            Modifier::Alt => modifiers.alt,
+        }
+    }
+}
+
+/// A mouse button, with a modifier, that uses a tool
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    pub button: Button,
+    pub modifier: Modifier,
+    /// `None` uses the tool that is picked in the toolbar
+    pub tool: Option<Tool>,
+}
+
+/// Which tool every mouse button uses, persisted in the browser
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct InputBindings(pub Vec<Binding>);
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let binding = |button, modifier, tool| Binding {
+            button,
+            modifier,
+            tool,
+        };
+
+        Self(vec![
+            binding(Button::Primary, Modifier::None, None),
+            binding(Button::Secondary, Modifier::None, Some(Tool::Erase)),
+            binding(Button::Middle, Modifier::None, Some(Tool::Pan)),
+            // trackpads have no middle button
+            binding(Button::Primary, Modifier::Alt, Some(Tool::Pan)),
+        ])
+    }
+}
+
+impl InputBindings {
+    /// The tool a press of `button` uses, bindings with a held modifier win over the ones without
+    pub fn tool(&self, button: Button, modifiers: Modifiers, active: Tool) -> Option<Tool> {
+        let bindings = self
+            .0
+            .iter()
+            .filter(|binding| binding.button == button && binding.modifier.is_held(modifiers));
+
+        let binding = bindings
+            .clone()
+            .find(|binding| binding.modifier != Modifier::None)
+            .or_else(|| bindings.clone().next())?;
+
+        Some(binding.tool.unwrap_or(active))
+    }
+
+    /// Editor of the bindings
+    pub fn ui(&mut self, ui: &mut Ui) {
+        let mut removed = None;
+
+        egui::Grid::new("input bindings").show(ui, |ui| {
+            for (i, binding) in self.0.iter_mut().enumerate() {
+                egui::ComboBox::from_id_source(("button", i))
+                    .selected_text(format!("{:?}", binding.button))
+                    .show_ui(ui, |ui| {
+                        for button in Button::ALL {
+                            ui.selectable_value(
+                                &mut binding.button,
+                                button,
+                                format!("{:?}", button),
+                            );
+                        }
+                    });
+
+                egui::ComboBox::from_id_source(("modifier", i))
+                    .selected_text(modifier_name(binding.modifier))
+                    .show_ui(ui, |ui| {
+                        for modifier in Modifier::ALL {
+                            ui.selectable_value(
+                                &mut binding.modifier,
+                                modifier,
+                                modifier_name(modifier),
+                            );
+                        }
+                    });
+
+                egui::ComboBox::from_id_source(("tool", i))
+                    .selected_text(tool_name(binding.tool))
+                    .show_ui(ui, |ui| {
+                        for tool in std::iter::once(None).chain(Tool::ALL.map(Some)) {
+                            ui.selectable_value(&mut binding.tool, tool, tool_name(tool));
+                        }
+                    });
+
+                if ui.button("🗑").clicked() {
+                    removed = Some(i);
+                }
+
+                ui.end_row();
+            }
+        });
+
+        if let Some(i) = removed {
+            self.0.remove(i);
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Add").clicked() {
+                self.0.push(Binding {
+                    button: Button::Primary,
+                    modifier: Modifier::Shift,
+                    tool: Some(Tool::Select),
+                });
+            }
+
+            if ui.button("Reset").clicked() {
+                *self = Self::default();
+            }
+        });
+    }
+}
+
+fn modifier_name(modifier: Modifier) -> &'static str {
+    match modifier {
+        Modifier::None => "Any keys",
+        Modifier::Shift => "Shift",
+        Modifier::Command => "Ctrl",
+        Modifier::Alt => "Alt",
+    }
+}
+
+fn tool_name(tool: Option<Tool>) -> &'static str {
+    match tool {
+        Some(tool) => tool.name(),
+        None => "Picked tool",
+    }
+}
@@ -0,0 +1,76 @@
+use egui::{emath::RectTransform, Pos2, Rect};
+use serde::{Deserialize, Serialize};
+
+/// Screen points per canvas unit the view can zoom to
+pub const MIN_SCALE: f32 = 0.01;
+pub const MAX_SCALE: f32 = 64.0;
+
+/// Where the map is on screen, the canvas position `p` is painted at `offset + p * scale`
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct View {
+    pub offset: Pos2,
+    pub scale: f32,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            offset: Pos2::ZERO,
+            scale: 1.0,
+        }
+    }
+}
+
+impl View {
+    /// From canvas coordinates in `canvas_rect` to the screen
+    pub fn to_screen(&self, canvas_rect: Rect) -> RectTransform {
+        let screen_rect = Rect::from_min_max(
+            self.offset + canvas_rect.min.to_vec2() * self.scale,
+            self.offset + canvas_rect.max.to_vec2() * self.scale,
+        );
+
+        RectTransform::from_to(canvas_rect, screen_rect)
+    }
+
+    /// Scales by `factor`, the canvas position under the screen position `center` stays where it is
+    pub fn zoom_around(&mut self, center: Pos2, factor: f32) {
+        if !factor.is_finite() || factor <= 0.0 {
+            return;
+        }
+
+        let scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+
+        // the clamped factor, so that the anchor also holds at the limits
+        let factor = scale / self.scale;
+
+        self.offset = center + (self.offset - center) * factor;
+        self.scale = scale;
+    }
+
+    /// Shows all of `canvas_rect` as large as possible, centered in `screen_rect`
+    pub fn fit(&mut self, canvas_rect: Rect, screen_rect: Rect) {
+        if canvas_rect.width() <= 0.0 || canvas_rect.height() <= 0.0 {
+            return;
+        }
+
+        self.scale = (screen_rect.width() / canvas_rect.width())
+            .min(screen_rect.height() / canvas_rect.height())
+            .clamp(MIN_SCALE, MAX_SCALE);
+
+        self.offset = screen_rect.center() - canvas_rect.center().to_vec2() * self.scale;
+    }
+
+    /// One canvas unit per screen point, around the screen position `center`
+    pub fn actual_size(&mut self, center: Pos2) {
+        self.zoom_around(center, 1.0 / self.scale);
+    }
+
+    /// Actual size with the canvas origin at the top left corner of `screen_rect`
+    pub fn reset(&mut self, screen_rect: Rect) {
+        *self = Self {
+            offset: screen_rect.min,
+            scale: 1.0,
+        };
+    }
+}
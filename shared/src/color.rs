@@ -0,0 +1,136 @@
+use std::{fmt::Display, str::FromStr};
+
+use egui::Color32;
+use serde::{de::Error as _, Deserialize, Serialize};
+use thiserror::Error;
+
+/// Unmultiplied sRGBA color.
+///
+/// Unlike [`Color32`], which is premultiplied, every value survives a round trip, also when the color is transparent.
+/// Serialized as `[r, g, b, a]` and deserialized from `[r, g, b, a]`, `[r, g, b]` or a hex string like `"#ff000080"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("invalid hex color {0:?}, expected #rgb, #rgba, #rrggbb or #rrggbbaa")]
+pub struct ParseColorError(pub String);
+
+impl Color {
+    pub const BLACK: Self = Self::from_rgb(0, 0, 0);
+    pub const RED: Self = Self::from_rgb(255, 0, 0);
+
+    pub const fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    pub const fn from_rgba_unmultiplied(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub const fn to_srgba_unmultiplied(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    /// Formats the color as `#rrggbbaa`
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::RED
+    }
+}
+
+impl From<Color> for Color32 {
+    fn from(color: Color) -> Self {
+        Color32::from_rgba_unmultiplied(color.r, color.g, color.b, color.a)
+    }
+}
+
+impl From<Color32> for Color {
+    fn from(color: Color32) -> Self {
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+
+        Self { r, g, b, a }
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    /// Parses `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, the `#` is optional
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || ParseColorError(value.to_string());
+
+        let hex = value.trim();
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+
+        // from_str_radix would also accept a sign
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+
+        let digits = match hex.len() {
+            3 | 4 => 1,
+            6 | 8 => 2,
+            _ => return Err(error()),
+        };
+
+        let channels = (0..hex.len() / digits)
+            .map(|i| u8::from_str_radix(&hex[i * digits..(i + 1) * digits], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| error())?;
+
+        // a single digit stands for both digits, i.e. f is ff
+        let channels: Vec<u8> = match digits {
+            1 => channels.iter().map(|channel| channel * 17).collect(),
+            _ => channels,
+        };
+
+        match channels[..] {
+            [r, g, b] => Ok(Self::from_rgb(r, g, b)),
+            [r, g, b, a] => Ok(Self::from_rgba_unmultiplied(r, g, b, a)),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_srgba_unmultiplied().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum ColorSchema {
+            Rgba([u8; 4]),
+            Rgb([u8; 3]),
+            Hex(String),
+        }
+
+        match ColorSchema::deserialize(deserializer) {
+            Ok(ColorSchema::Rgba([r, g, b, a])) => Ok(Self::from_rgba_unmultiplied(r, g, b, a)),
+            Ok(ColorSchema::Rgb([r, g, b])) => Ok(Self::from_rgb(r, g, b)),
+            Ok(ColorSchema::Hex(hex)) => hex.parse().map_err(D::Error::custom),
+            Err(_) => Err(D::Error::custom(
+                "expected a color as [r, g, b, a], [r, g, b] or hex string",
+            )),
+        }
+    }
+}
@@ -1,12 +1,14 @@
-use std::{io::Write, sync::RwLock};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
 
-use config::{ConfigError, FileFormat};
+use config::{FileFormat, FileSourceString};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use indoc::indoc;
 
-use lazy_static::lazy_static;
-
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Website {
     pub title: String,
@@ -21,9 +23,13 @@ impl Default for Website {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Host {
+    /// Address the website tells browsers to connect to
     pub ip: String,
     pub port: u16,
+    /// Address the server listens on
+    pub bind: String,
 }
 
 impl Default for Host {
@@ -31,40 +37,228 @@ impl Default for Host {
         Self {
             ip: "127.0.0.1".to_string(),
             port: 8439,
+            bind: "0.0.0.0".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Server {
+    /// Directory containing `public/` (and the map images in `assets/images/`)
+    pub data_dir: PathBuf,
+    /// One of `off`, `error`, `warn`, `info`, `debug` or `trace`
+    pub log_level: String,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Self {
+            data_dir: PathBuf::from("."),
+            log_level: "debug".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Cors {
+    /// Value of the `Access-Control-Allow-Origin` header
+    pub allow_origin: String,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Self {
+            allow_origin: "*".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Largest accepted request body in bytes
+    pub max_body_size: usize,
+    /// Largest number of lines a board can hold
+    pub max_lines: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_body_size: 16 * 1024 * 1024,
+            max_lines: 100_000,
         }
     }
 }
 
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Config {
     pub host: Host,
     pub website: Website,
+    pub server: Server,
+    pub cors: Cors,
+    pub limits: Limits,
 }
 
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Environment variables starting with this prefix override values from the configuration file.
+/// Nested keys are separated by `__`, e.g. `SYNCED_DRAWING_HOST__PORT=8000` or `SYNCED_DRAWING_WEBSITE__TITLE=Maps`.
+pub const ENV_PREFIX: &str = "SYNCED_DRAWING";
+
 impl Config {
+    /// Loads [`DEFAULT_CONFIG_PATH`] from the current directory
     pub fn new() -> Result<Self, ConfigError> {
+        Config::load(Path::new(DEFAULT_CONFIG_PATH))
+    }
+
+    /// Reads the configuration from the TOML file at `path` and applies the environment overrides (see [`ENV_PREFIX`]) on top.
+    /// The result is not validated, callers apply their own overrides first and then call [`Config::validate`].
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        if !path.exists() {
+            return Err(ConfigError::MissingFile(path.to_path_buf()));
+        }
+
+        let content = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        // parsed on its own first, the errors of the config crate do not carry a position
+        if let Err(e) = toml::from_str::<Config>(&content) {
+            let (line, column) = match e.span() {
+                Some(span) => line_column(&content, span.start),
+                None => (0, 0),
+            };
+
+            return Err(ConfigError::Parse {
+                path: path.to_path_buf(),
+                line,
+                column,
+                message: e.message().to_string(),
+            });
+        }
+
+        Config::build(Some(config::File::from_str(&content, FileFormat::Toml)))
+    }
+
+    /// Builds the configuration from the defaults and the environment overrides only
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Config::build(None)
+    }
+
+    fn build(
+        file: Option<config::File<FileSourceString, FileFormat>>,
+    ) -> Result<Self, ConfigError> {
         let mut builder = config::Config::builder();
 
-        match std::path::Path::new("config.toml").exists() {
-            true => {
-                builder = builder.add_source(config::File::new("config.toml", FileFormat::Toml));
+        if let Some(file) = file {
+            builder = builder.add_source(file);
+        }
+
+        let map_error = |e: config::ConfigError| match e {
+            config::ConfigError::Type { ref key, .. } => ConfigError::InvalidValue {
+                key: key.clone().unwrap_or_default(),
+                message: e.to_string(),
+            },
+            e => ConfigError::Source(e),
+        };
+
+        let built = builder
+            .add_source(
+                config::Environment::with_prefix(ENV_PREFIX)
+                    .prefix_separator("_")
+                    .separator("__"),
+            )
+            .build()
+            .map_err(map_error)?;
+
+        // the config crate truncates integers that do not fit into a u16 instead of failing
+        if let Ok(port) = built.get_int("host.port") {
+            if !(1..=u16::MAX as i64).contains(&port) {
+                return Err(ConfigError::InvalidValue {
+                    key: "host.port".to_string(),
+                    message: format!("{} is not between 1 and 65535", port),
+                });
             }
-            false => {
-                let mut file = std::fs::File::create("config.toml").unwrap();
+        }
 
-                let config = Config::default();
+        built.try_deserialize().map_err(map_error)
+    }
 
-                let config_string = toml::to_string_pretty(&config).unwrap();
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |key: &str, message: String| {
+            Err(ConfigError::InvalidValue {
+                key: key.to_string(),
+                message,
+            })
+        };
 
-                file.write_all(config_string.as_bytes()).unwrap();
+        if self.host.port == 0 {
+            return invalid("host.port", "must be between 1 and 65535".to_string());
+        }
 
-                println!("config.toml created. Please edit config.toml and run again.");
-                std::process::exit(0);
-            }
+        if self.host.bind.trim().is_empty() {
+            return invalid("host.bind", "must not be empty".to_string());
+        }
+
+        if !LOG_LEVELS.contains(&self.server.log_level.to_lowercase().as_str()) {
+            return invalid(
+                "server.log_level",
+                format!(
+                    "{} is not one of {}",
+                    self.server.log_level,
+                    LOG_LEVELS.join(", ")
+                ),
+            );
+        }
+
+        if self.cors.allow_origin.is_empty()
+            || !self
+                .cors
+                .allow_origin
+                .chars()
+                .all(|c| c.is_ascii_graphic() || c == ' ')
+        {
+            return invalid(
+                "cors.allow_origin",
+                format!("{:?} is not a valid header value", self.cors.allow_origin),
+            );
+        }
+
+        if self.limits.max_body_size == 0 {
+            return invalid("limits.max_body_size", "must be greater than 0".to_string());
+        }
+
+        if self.limits.max_lines == 0 {
+            return invalid("limits.max_lines", "must be greater than 0".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Writes the default configuration to `path`, overwriting any existing file
+    pub fn write_default(path: &Path) -> Result<(), ConfigError> {
+        let config_string = Config::default().to_toml()?;
+
+        let io_error = |source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
         };
 
-        let config: Config = builder.build().unwrap().try_deserialize()?;
+        let mut file = std::fs::File::create(path).map_err(io_error)?;
 
+        file.write_all(config_string.as_bytes()).map_err(io_error)
+    }
+
+    pub fn to_toml(&self) -> Result<String, ConfigError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn print(&self) -> Result<(), ConfigError> {
         print!(indoc!(
             "
             -------------
@@ -73,12 +267,53 @@ impl Config {
             "
         ));
 
-        println!("{}", toml::to_string_pretty(&config).unwrap());
+        println!("{}", self.to_toml()?);
+
+        Ok(())
+    }
 
-        Ok(config)
+    pub fn data_path(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.server.data_dir.join(path)
     }
 }
 
-lazy_static! {
-    pub static ref CONFIG: RwLock<Config> = RwLock::new(Config::new().unwrap());
+const LOG_LEVELS: [&str; 6] = ["off", "error", "warn", "info", "debug", "trace"];
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("configuration file {0} not found")]
+    MissingFile(PathBuf),
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path} at line {line}, column {column}: {message}")]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    #[error("invalid value for {key}: {message}")]
+    InvalidValue { key: String, message: String },
+    #[error("failed to serialize configuration: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error(transparent)]
+    Source(config::ConfigError),
+}
+
+/// 1-based line and column of the byte `offset` in `content`
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+
+    let line = before.matches('\n').count() + 1;
+
+    let column = match before.rfind('\n') {
+        Some(newline) => before[newline + 1..].chars().count() + 1,
+        None => before.chars().count() + 1,
+    };
+
+    (line, column)
 }
@@ -0,0 +1,199 @@
+//! Grid over a map that points can snap to, in canvas coordinates.
+
+use egui::{Pos2, Rect, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::color::Color;
+
+/// Grids with more cells than this along a side of the shown area are not drawn
+pub const MAX_CELLS_PER_SIDE: f32 = 200.0;
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GridKind {
+    #[default]
+    Square,
+    /// Pointy topped hexagons in rows, every other row is shifted by half a cell
+    Hex,
+}
+
+/// What drawn points snap to
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Snap {
+    #[default]
+    None,
+    /// Corners of the cells
+    Intersections,
+    Centers,
+}
+
+/// Grid of a map, shared by everyone in the room
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct Grid {
+    pub is_visible: bool,
+    pub kind: GridKind,
+    /// Distance between the centers of neighbouring cells in canvas units
+    pub cell_size: f32,
+    /// Where the corner of a square cell or the center of a hex cell is, in canvas units
+    pub offset: [f32; 2],
+    pub color: Color,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            is_visible: false,
+            kind: GridKind::Square,
+            cell_size: 50.0,
+            offset: [0.0, 0.0],
+            color: Color::from_rgba_unmultiplied(0, 0, 0, 96),
+        }
+    }
+}
+
+impl Grid {
+    pub fn is_valid(&self) -> bool {
+        self.cell_size.is_finite()
+            && self.cell_size > 0.0
+            && self.offset.iter().all(|offset| offset.is_finite())
+    }
+
+    fn offset(&self) -> Vec2 {
+        Vec2::from(self.offset)
+    }
+
+    /// Distance from the center of a hex cell to its corners
+    fn hex_radius(&self) -> f32 {
+        self.cell_size / 3f32.sqrt()
+    }
+
+    /// Distance between two rows of hex cells
+    fn hex_row_height(&self) -> f32 {
+        1.5 * self.hex_radius()
+    }
+
+    /// Center of the hex cell in `column` of `row`
+    fn hex_center(&self, column: i64, row: i64) -> Pos2 {
+        let shift = match row.rem_euclid(2) {
+            0 => 0.0,
+            _ => self.cell_size / 2.0,
+        };
+
+        Pos2::new(
+            column as f32 * self.cell_size + shift,
+            row as f32 * self.hex_row_height(),
+        ) + self.offset()
+    }
+
+    /// Corners of the hex cell around `center`
+    fn hex_corners(&self, center: Pos2) -> [Pos2; 6] {
+        let radius = self.hex_radius();
+
+        std::array::from_fn(|i| {
+            let angle = (30.0 + 60.0 * i as f32).to_radians();
+
+            center + Vec2::angled(angle) * radius
+        })
+    }
+
+    /// Center of the cell that contains `pos`
+    fn cell_center(&self, pos: Pos2) -> Pos2 {
+        match self.kind {
+            GridKind::Square => {
+                let cell = ((pos - self.offset()).to_vec2() / self.cell_size).floor();
+
+                ((cell + Vec2::splat(0.5)) * self.cell_size + self.offset()).to_pos2()
+            }
+            // the closest center, rounding alone picks the wrong cell near the slanted sides
+            GridKind::Hex => {
+                let row = ((pos.y - self.offset[1]) / self.hex_row_height()).round() as i64;
+
+                (row - 1..=row + 1)
+                    .flat_map(|row| {
+                        let shift = self.hex_center(0, row).x;
+                        let column = ((pos.x - shift) / self.cell_size).round() as i64;
+
+                        (column - 1..=column + 1).map(move |column| (column, row))
+                    })
+                    .map(|(column, row)| self.hex_center(column, row))
+                    .min_by(|a, b| a.distance_sq(pos).total_cmp(&b.distance_sq(pos)))
+                    .unwrap_or(pos)
+            }
+        }
+    }
+
+    /// The intersection or cell center closest to `pos`
+    pub fn snap(&self, pos: Pos2, snap: Snap) -> Pos2 {
+        if !self.is_valid() {
+            return pos;
+        }
+
+        match (snap, self.kind) {
+            (Snap::None, _) => pos,
+            (Snap::Centers, _) => self.cell_center(pos),
+            (Snap::Intersections, GridKind::Square) => {
+                let cell = ((pos - self.offset()).to_vec2() / self.cell_size).round();
+
+                (cell * self.cell_size + self.offset()).to_pos2()
+            }
+            // the closest corner is one of the cell the point is in
+            (Snap::Intersections, GridKind::Hex) => self
+                .hex_corners(self.cell_center(pos))
+                .into_iter()
+                .min_by(|a, b| a.distance_sq(pos).total_cmp(&b.distance_sq(pos)))
+                .unwrap_or(pos),
+        }
+    }
+
+    /// Polylines that draw the grid inside `rect`, empty if the cells are too small to be seen
+    pub fn paths(&self, rect: Rect) -> Vec<Vec<Pos2>> {
+        if !self.is_valid() || rect.size().max_elem() / self.cell_size > MAX_CELLS_PER_SIDE {
+            return Vec::new();
+        }
+
+        match self.kind {
+            GridKind::Square => {
+                let first = ((rect.min - self.offset()).to_vec2() / self.cell_size).ceil();
+                let last = ((rect.max - self.offset()).to_vec2() / self.cell_size).floor();
+
+                let columns = (first.x as i64..=last.x as i64).map(|column| {
+                    let x = column as f32 * self.cell_size + self.offset[0];
+
+                    vec![Pos2::new(x, rect.min.y), Pos2::new(x, rect.max.y)]
+                });
+
+                let rows = (first.y as i64..=last.y as i64).map(|row| {
+                    let y = row as f32 * self.cell_size + self.offset[1];
+
+                    vec![Pos2::new(rect.min.x, y), Pos2::new(rect.max.x, y)]
+                });
+
+                columns.chain(rows).collect()
+            }
+            GridKind::Hex => {
+                let row_height = self.hex_row_height();
+
+                // cells that reach into the rect by their corners are included
+                let first_row = ((rect.min.y - self.offset[1]) / row_height).floor() as i64 - 1;
+                let last_row = ((rect.max.y - self.offset[1]) / row_height).ceil() as i64 + 1;
+
+                let first_column =
+                    ((rect.min.x - self.offset[0]) / self.cell_size).floor() as i64 - 1;
+                let last_column =
+                    ((rect.max.x - self.offset[0]) / self.cell_size).ceil() as i64 + 1;
+
+                (first_row..=last_row)
+                    .flat_map(|row| (first_column..=last_column).map(move |column| (column, row)))
+                    .map(|(column, row)| {
+                        let mut corners = self.hex_corners(self.hex_center(column, row)).to_vec();
+                        corners.push(corners[0]);
+
+                        corners
+                    })
+                    .collect()
+            }
+        }
+    }
+}
@@ -1,9 +1,24 @@
+pub mod color;
 pub mod config;
+pub mod grid;
+pub mod measure;
+pub mod schema;
+pub mod shape;
+pub mod smoothing;
+pub mod snapshot;
+pub mod style;
+pub mod text;
 
 use std::{collections::HashMap, fmt::Display, ops::Deref};
 
-use egui::{ahash::HashSet, Color32, Pos2, Rgba, Stroke};
-use serde::{Deserialize, Serialize};
+use color::Color;
+use egui::{ahash::HashSet, Pos2, Rect, Stroke, Vec2};
+use serde::{de::Error as _, Deserialize, Serialize};
+use serde_json::Value;
+use shape::{shape_paths, ShapeKind, ShapePath};
+use smoothing::StrokeFilter;
+use style::StrokeStyle;
+use text::Text;
 
 use anyhow::Result;
 
@@ -33,6 +48,13 @@ impl ClientID {
     }
 }
 
+impl Default for ClientID {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A position in canvas coordinates, i.e. pixels of the map image
 #[derive(Debug, Clone, PartialEq)]
 pub struct SPos2(pub Pos2);
 
@@ -64,51 +86,40 @@ pub enum Flag {
     Clear,
 }
 
-#[derive(Debug, Clone)]
-pub struct StrokeX(pub Stroke);
+/// Stroke of a line. Unlike [`Stroke`] the color is unmultiplied, so it survives serialization unchanged.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct StrokeX {
+    pub color: Color,
+    pub width: f32,
+}
 
 impl Default for StrokeX {
     fn default() -> Self {
-        Self(Stroke {
-            color: Color32::RED.into(),
+        Self {
+            color: Color::RED,
             width: 5.0,
-            ..Default::default()
-        })
+        }
     }
 }
 
-impl Serialize for StrokeX {
-    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let color = self.0.color;
-        let width = self.0.width;
-
-        (color.r(), color.g(), color.b(), color.a(), width).serialize(serializer)
+impl StrokeX {
+    pub fn new(width: f32, color: impl Into<Color>) -> Self {
+        Self {
+            color: color.into(),
+            width,
+        }
     }
 }
 
-impl<'de> Deserialize<'de> for StrokeX {
-    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let (r, g, b, a, width) = Deserialize::deserialize(deserializer)?;
-
-        Ok(Self(Stroke {
-            color: Rgba::from_rgba_premultiplied(r, g, b, a).into(),
-            width,
-            ..Default::default()
-        }))
+impl From<Stroke> for StrokeX {
+    fn from(stroke: Stroke) -> Self {
+        Self::new(stroke.width, stroke.color)
     }
 }
 
-impl Deref for StrokeX {
-    type Target = Stroke;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl From<StrokeX> for Stroke {
+    fn from(stroke: StrokeX) -> Self {
+        Stroke::new(stroke.width, stroke.color)
     }
 }
 
@@ -116,6 +127,11 @@ impl Deref for StrokeX {
 pub struct Line {
     pub coordinates: Vec<SPos2>,
     pub stroke: StrokeX,
+    pub style: StrokeStyle,
+    pub shape: ShapeKind,
+    /// Pen pressure from 0 to 1 at each coordinate, the stroke is `pressure * stroke.width` wide there.
+    /// Empty for a uniform width.
+    pub pressure: Vec<f32>,
     pub flag: Option<Flag>,
 }
 
@@ -124,14 +140,273 @@ impl Line {
         Self {
             coordinates: Vec::new(),
             stroke: StrokeX::default(),
+            style: StrokeStyle::default(),
+            shape: ShapeKind::default(),
+            pressure: Vec::new(),
             flag: None,
         }
     }
+
+    /// Paths the line is stroked along, in canvas coordinates
+    pub fn paths(&self) -> Vec<ShapePath> {
+        let points: Vec<Pos2> = self.coordinates.iter().map(|p| p.0).collect();
+
+        shape_paths(&self.shape, &points, self.stroke.width)
+    }
+
+    /// Whether there is anything to paint
+    pub fn is_drawable(&self) -> bool {
+        match &self.shape {
+            ShapeKind::Text(text) => !text.is_empty(),
+            _ => self.coordinates.len() >= 2,
+        }
+    }
+
+    pub fn text(&self) -> Option<&Text> {
+        match &self.shape {
+            ShapeKind::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Width of the stroke at each coordinate, if freehand lines have a pressure for every coordinate
+    pub fn widths(&self) -> Option<Vec<f32>> {
+        let has_pressure = self.shape == ShapeKind::Freehand
+            && !self.pressure.is_empty()
+            && self.pressure.len() == self.coordinates.len();
+
+        has_pressure.then(|| {
+            self.pressure
+                .iter()
+                .map(|pressure| pressure * self.stroke.width)
+                .collect()
+        })
+    }
+
+    /// Pieces of a stroke with pressure with the width at each point, one for every dash of its pattern.
+    /// The pieces are painted as filled outlines without caps, so the dashes include them.
+    pub fn pressure_parts(&self) -> Option<Vec<(Vec<Pos2>, Vec<f32>)>> {
+        let widths = self.widths()?;
+
+        let points: Vec<Pos2> = self.coordinates.iter().map(|p| p.0).collect();
+
+        Some(match self.style.pattern_array(self.stroke.width) {
+            Some([dash, gap]) => style::width_dashes(&points, &widths, dash, gap),
+            None => vec![(points, widths)],
+        })
+    }
+
+    /// Pressure of the coordinate closest to each of `points`, for points derived from the coordinates
+    fn pressure_at(&self, points: &[Pos2]) -> Vec<f32> {
+        if self.pressure.len() != self.coordinates.len() {
+            return Vec::new();
+        }
+
+        points
+            .iter()
+            .filter_map(|point| {
+                self.coordinates
+                    .iter()
+                    .zip(self.pressure.iter())
+                    .min_by(|(a, _), (b, _)| {
+                        a.0.distance_sq(*point).total_cmp(&b.0.distance_sq(*point))
+                    })
+                    .map(|(_, pressure)| *pressure)
+            })
+            .collect()
+    }
+
+    /// Color the inside of a closed shape is filled with
+    pub fn fill(&self) -> Option<Color> {
+        match self.shape {
+            ShapeKind::Polygon { fill } => fill,
+            _ => None,
+        }
+    }
+
+    /// Whether `pos` is within `tolerance` of the stroke or inside the fill
+    pub fn hit_test(&self, pos: Pos2, tolerance: f32) -> bool {
+        if let Some(text) = self.text() {
+            return text.rect().expand(tolerance).contains(pos);
+        }
+
+        let paths = self.paths();
+
+        let max_distance = tolerance + self.stroke.width / 2.0;
+
+        let is_on_stroke = paths.iter().any(|path| {
+            path.segments()
+                .any(|segment| shape::distance_to_segment(pos, segment) <= max_distance)
+                || (path.points.len() == 1 && path.points[0].distance(pos) <= max_distance)
+        });
+
+        is_on_stroke
+            || (self.fill().is_some()
+                && paths
+                    .first()
+                    .is_some_and(|path| shape::is_inside_polygon(pos, &path.points)))
+    }
+
+    /// Bounding box of the paths, grown by half the stroke width, or of the text
+    pub fn bounding_rect(&self) -> Rect {
+        if let Some(text) = self.text() {
+            return text.rect();
+        }
+
+        let points: Vec<Pos2> = self
+            .paths()
+            .into_iter()
+            .flat_map(|path| path.points)
+            .collect();
+
+        Rect::from_points(&points).expand(self.stroke.width / 2.0)
+    }
+
+    /// Cuts the circle of `radius` around `pos` out of the stroke.
+    /// Returns `None` if the line is not hit, otherwise the freehand lines that remain.
+    /// Text and filled shapes can't be cut, they are erased completely.
+    pub fn erase(&self, pos: Pos2, radius: f32) -> Option<Vec<Line>> {
+        if !self.hit_test(pos, radius) {
+            return None;
+        }
+
+        if self.text().is_some() || self.fill().is_some() {
+            return Some(Vec::new());
+        }
+
+        let remainders = self
+            .paths()
+            .into_iter()
+            .flat_map(|path| shape::cut_path(&path, pos, radius + self.stroke.width / 2.0))
+            .map(|points| Line {
+                pressure: self.pressure_at(&points),
+                coordinates: points.into_iter().map(SPos2).collect(),
+                stroke: self.stroke,
+                style: self.style,
+                shape: ShapeKind::Freehand,
+                flag: None,
+            })
+            .collect();
+
+        Some(remainders)
+    }
+
+    /// Smooths and simplifies freehand lines, the corners of the other shapes are kept
+    pub fn smooth(&mut self, filter: &StrokeFilter) {
+        if self.shape != ShapeKind::Freehand {
+            return;
+        }
+
+        let points: Vec<Pos2> = self.coordinates.iter().map(|p| p.0).collect();
+
+        let smoothed = filter.apply(&points);
+
+        self.pressure = self.pressure_at(&smoothed);
+        self.coordinates = smoothed.into_iter().map(SPos2).collect();
+    }
+
+    /// Whether the whole line lies inside `polygon`, for text its whole rect
+    pub fn is_inside(&self, polygon: &[Pos2]) -> bool {
+        if !self.is_drawable() {
+            return false;
+        }
+
+        let points = match self.text() {
+            Some(text) => {
+                let rect = text.rect();
+
+                vec![
+                    rect.left_top(),
+                    rect.right_top(),
+                    rect.right_bottom(),
+                    rect.left_bottom(),
+                ]
+            }
+            None => self.coordinates.iter().map(|p| p.0).collect(),
+        };
+
+        points
+            .iter()
+            .all(|point| shape::is_inside_polygon(*point, polygon))
+    }
+
+    pub fn translate(&mut self, delta: Vec2) {
+        for coordinate in self.coordinates.iter_mut() {
+            coordinate.0 += delta;
+        }
+
+        if let ShapeKind::Text(text) = &mut self.shape {
+            text.anchor.0 += delta;
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+/// Rect the background image covers in canvas coordinates.
+/// Canvas coordinates are pixels of the background image, so lines keep their place on the map regardless of window size, zoom and offset.
+pub fn background_canvas_rect(background_size: [usize; 2]) -> Rect {
+    Rect::from_min_size(
+        Pos2::ZERO,
+        Vec2::new(background_size[0] as f32, background_size[1] as f32),
+    )
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Lines(pub HashMap<usize, Line>);
 
+/// Serialized form of [`Lines`], see [`schema`]
+#[derive(Deserialize, Serialize)]
+struct LinesSchema<L> {
+    schema: u32,
+    lines: L,
+}
+
+impl Serialize for Lines {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        LinesSchema {
+            schema: schema::SCHEMA_VERSION,
+            lines: &self.0,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Lines {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+
+        // version 0 has no wrapper object
+        let LinesSchema { schema, lines } = match value.get("schema") {
+            Some(_) => serde_json::from_value::<LinesSchema<HashMap<usize, Value>>>(value),
+            None => serde_json::from_value(value).map(|lines| LinesSchema { schema: 0, lines }),
+        }
+        .map_err(D::Error::custom)?;
+
+        if schema > schema::SCHEMA_VERSION {
+            return Err(D::Error::custom(schema::SchemaError::UnsupportedVersion(
+                schema,
+            )));
+        }
+
+        lines
+            .into_iter()
+            .map(|(line_id, line)| {
+                let line = schema::migrate_line(line, schema).map_err(D::Error::custom)?;
+
+                let line = serde_json::from_value::<Line>(line).map_err(D::Error::custom)?;
+
+                Ok((line_id, line))
+            })
+            .collect::<std::result::Result<_, _>>()
+            .map(Self)
+    }
+}
+
 impl Deref for Lines {
     type Target = HashMap<usize, Line>;
 
@@ -169,6 +444,14 @@ impl Lines {
     pub fn clear(&mut self) {
         self.0.clear();
     }
+
+    /// Bounding box of all lines that are drawn
+    pub fn bounding_rect(&self) -> Option<Rect> {
+        self.values()
+            .filter(|line| line.is_drawable())
+            .map(Line::bounding_rect)
+            .reduce(|a, b| a.union(b))
+    }
 }
 
 #[derive(Debug)]
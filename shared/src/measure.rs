@@ -0,0 +1,69 @@
+//! Distances and areas on a map in the units of the map.
+
+use egui::Pos2;
+use serde::{Deserialize, Serialize};
+
+/// Scale of a map, lengths in canvas units (pixels of the background) are divided by `pixels_per_unit`.
+/// Embedded from a `<map>.json` file next to the background image, or measured by the user.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub pixels_per_unit: f32,
+    pub unit: String,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            pixels_per_unit: 1.0,
+            unit: "px".to_string(),
+        }
+    }
+}
+
+impl Calibration {
+    /// The calibration in which a path of `pixels` is `length` units long
+    pub fn from_measurement(pixels: f32, length: f32, unit: &str) -> Option<Self> {
+        let pixels_per_unit = pixels / length;
+
+        (pixels_per_unit.is_finite() && pixels_per_unit > 0.0).then(|| Self {
+            pixels_per_unit,
+            unit: unit.trim().to_string(),
+        })
+    }
+
+    pub fn length(&self, pixels: f32) -> f32 {
+        pixels / self.pixels_per_unit
+    }
+
+    pub fn area(&self, square_pixels: f32) -> f32 {
+        square_pixels / (self.pixels_per_unit * self.pixels_per_unit)
+    }
+
+    pub fn format_length(&self, pixels: f32) -> String {
+        format!("{:.2} {}", self.length(pixels), self.unit)
+    }
+
+    pub fn format_area(&self, square_pixels: f32) -> String {
+        format!("{:.2} {}²", self.area(square_pixels), self.unit)
+    }
+}
+
+/// Length of the polyline through `points`
+pub fn path_length(points: &[Pos2]) -> f32 {
+    points
+        .windows(2)
+        .map(|segment| segment[0].distance(segment[1]))
+        .sum()
+}
+
+/// Area of the polygon with the corners `points`, regardless of their orientation.
+/// Parts of self-intersecting polygons that wind in opposite directions cancel out.
+pub fn polygon_area(points: &[Pos2]) -> f32 {
+    let twice_area: f32 = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum();
+
+    twice_area.abs() / 2.0
+}
@@ -0,0 +1,152 @@
+//! Versions of the serialized form of [`Lines`](crate::Lines).
+//!
+//! Lines are serialized together with the version of the schema they were written with:
+//!
+//! ```json
+//! { "schema": 1, "lines": { "42": <line> } }
+//! ```
+//!
+//! Payloads of older versions are upgraded line by line with [`migrate_line`] before they are deserialized,
+//! so saved boards and clients that still send an older version keep working.
+//! Versions newer than [`SCHEMA_VERSION`] are rejected.
+//!
+//! # Versions
+//!
+//! - 0: the unversioned form, `lines` is sent without the wrapper object.
+//!   A line is `{ "coordinates": [[x, y], ...], "stroke": [r, g, b, a, width], "flag": null }`.
+//! - 1: the lines are wrapped in the object above, a line is unchanged.
+//! - 2: the stroke is an object, `{ "color": [r, g, b, a], "width": width }`.
+//!   Color channels are unmultiplied sRGBA from 0 to 255, see [`Color`]. The color may also be a hex string.
+//! - 3: a line has a `style`, `{ "pattern": "solid", "opacity": 1.0, "cap": "butt", "highlighter": false }`,
+//!   see [`StrokeStyle`].
+//! - 4: a line has a `shape`, `{ "kind": "freehand" }`, see [`ShapeKind`].
+//! - 5: text shapes, `{ "kind": "text", "anchor": [x, y], "content": "...", "font_size": 24.0 }`.
+//!   Older lines are unchanged, the version only keeps older readers from loading text.
+//! - 6: a line has a `pressure` for each coordinate, `[]` for a uniform width.
+//!
+//! # Coordinates
+//!
+//! In every version coordinates are pixels of the map image, see [`background_canvas_rect`](crate::background_canvas_rect),
+//! so migrations leave them as they are.
+//! Before the SVG export, clients stored positions relative to the canvas widget, which depended on the size of the browser window.
+//! Those lines only lived in the memory of the server, boards were first saved in version 0 with map pixels, so there is nothing to convert.
+//!
+//! Changing the serialized form of [`Line`](crate::Line), [`StrokeX`](crate::StrokeX) or [`SPos2`](crate::SPos2)
+//! requires a new version: raise [`SCHEMA_VERSION`], add a migration from the previous version to [`MIGRATIONS`]
+//! and add a fixture to `shared/tests/fixtures`.
+
+use egui::Color32;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::{color::Color, shape::ShapeKind, style::StrokeStyle};
+
+pub const SCHEMA_VERSION: u32 = 6;
+
+/// Upgrades a single line from version `n` to `n + 1`, where `n` is the index
+type Migration = fn(Value) -> Result<Value, SchemaError>;
+
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [
+    unchanged,
+    stroke_object,
+    default_style,
+    freehand_shape,
+    unchanged,
+    uniform_pressure,
+];
+
+#[derive(Error, Debug)]
+pub enum SchemaError {
+    #[error("schema version {0} is newer than the supported version {SCHEMA_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("invalid line for schema version {version}: {message}")]
+    InvalidLine { version: u32, message: String },
+}
+
+/// Upgrades a line that was serialized with schema `version` to [`SCHEMA_VERSION`]
+pub fn migrate_line(line: Value, version: u32) -> Result<Value, SchemaError> {
+    if version > SCHEMA_VERSION {
+        return Err(SchemaError::UnsupportedVersion(version));
+    }
+
+    MIGRATIONS[version as usize..]
+        .iter()
+        .try_fold(line, |line, migration| migration(line))
+}
+
+/// 0 -> 1: only the wrapper object was added, 4 -> 5: only a shape was added
+fn unchanged(line: Value) -> Result<Value, SchemaError> {
+    Ok(line)
+}
+
+/// 1 -> 2: `[r, g, b, a, width]` with premultiplied channels becomes `{ "color": [r, g, b, a], "width": width }` with unmultiplied ones
+fn stroke_object(mut line: Value) -> Result<Value, SchemaError> {
+    let invalid = |message: &str| SchemaError::InvalidLine {
+        version: 1,
+        message: message.to_string(),
+    };
+
+    let stroke = line
+        .get_mut("stroke")
+        .ok_or_else(|| invalid("missing stroke"))?;
+
+    let (r, g, b, a, width) = serde_json::from_value::<(u8, u8, u8, u8, f32)>(stroke.take())
+        .map_err(|_| invalid("expected stroke [r, g, b, a, width]"))?;
+
+    let color = Color::from(Color32::from_rgba_premultiplied(r, g, b, a));
+
+    *stroke = json!({ "color": color, "width": width });
+
+    Ok(line)
+}
+
+/// 2 -> 3: lines get the default style, a solid stroke with butt caps
+fn default_style(mut line: Value) -> Result<Value, SchemaError> {
+    match line.as_object_mut() {
+        Some(line) => {
+            line.insert("style".to_string(), json!(StrokeStyle::default()));
+        }
+        None => {
+            return Err(SchemaError::InvalidLine {
+                version: 2,
+                message: "expected an object".to_string(),
+            })
+        }
+    }
+
+    Ok(line)
+}
+
+/// 3 -> 4: lines become freehand shapes
+fn freehand_shape(mut line: Value) -> Result<Value, SchemaError> {
+    match line.as_object_mut() {
+        Some(line) => {
+            line.insert("shape".to_string(), json!(ShapeKind::Freehand));
+        }
+        None => {
+            return Err(SchemaError::InvalidLine {
+                version: 3,
+                message: "expected an object".to_string(),
+            })
+        }
+    }
+
+    Ok(line)
+}
+
+/// 5 -> 6: lines have a uniform width
+fn uniform_pressure(mut line: Value) -> Result<Value, SchemaError> {
+    match line.as_object_mut() {
+        Some(line) => {
+            line.insert("pressure".to_string(), json!([]));
+        }
+        None => {
+            return Err(SchemaError::InvalidLine {
+                version: 5,
+                message: "expected an object".to_string(),
+            })
+        }
+    }
+
+    Ok(line)
+}
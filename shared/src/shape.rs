@@ -0,0 +1,381 @@
+use egui::{Pos2, Rect, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::{color::Color, text::Text};
+
+/// Points an ellipse is approximated with
+pub const ELLIPSE_SEGMENTS: usize = 64;
+
+/// What the coordinates of a [`Line`](crate::Line) describe
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ShapeKind {
+    /// Every coordinate is a point of the stroke
+    #[default]
+    Freehand,
+    /// From the first to the last coordinate
+    Segment,
+    /// Like [`ShapeKind::Segment`] with a head at the last coordinate
+    Arrow,
+    /// Axis aligned, the first and last coordinate are opposite corners
+    Rectangle,
+    /// Axis aligned, fits into the rectangle of the first and last coordinate
+    Ellipse,
+    /// Closed, every coordinate is a corner
+    Polygon { fill: Option<Color> },
+    /// Placed at its own anchor, the coordinates are unused
+    Text(Text),
+}
+
+impl ShapeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShapeKind::Freehand => "Freehand",
+            ShapeKind::Segment => "Segment",
+            ShapeKind::Arrow => "Arrow",
+            ShapeKind::Rectangle => "Rectangle",
+            ShapeKind::Ellipse => "Ellipse",
+            ShapeKind::Polygon { .. } => "Polygon",
+            ShapeKind::Text(_) => "Text",
+        }
+    }
+
+    /// Shapes that are defined by where a drag starts and ends, the others collect every point
+    pub fn is_two_point(&self) -> bool {
+        matches!(
+            self,
+            ShapeKind::Segment | ShapeKind::Arrow | ShapeKind::Rectangle | ShapeKind::Ellipse
+        )
+    }
+}
+
+/// A polyline that is stroked, closed ones connect the last point to the first
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapePath {
+    pub points: Vec<Pos2>,
+    pub closed: bool,
+}
+
+impl ShapePath {
+    fn open(points: Vec<Pos2>) -> Self {
+        Self {
+            points,
+            closed: false,
+        }
+    }
+
+    fn closed(points: Vec<Pos2>) -> Self {
+        Self {
+            points,
+            closed: true,
+        }
+    }
+
+    /// Line segments of the path, including the closing one
+    pub fn segments(&self) -> impl Iterator<Item = (Pos2, Pos2)> + '_ {
+        let closing = match (self.closed, self.points.first(), self.points.last()) {
+            (true, Some(first), Some(last)) if self.points.len() > 2 => Some((*last, *first)),
+            _ => None,
+        };
+
+        self.points
+            .windows(2)
+            .map(|segment| (segment[0], segment[1]))
+            .chain(closing)
+    }
+}
+
+/// Paths that draw `kind` through `points` with a stroke of `width`
+pub fn shape_paths(kind: &ShapeKind, points: &[Pos2], width: f32) -> Vec<ShapePath> {
+    let (first, last) = match (points.first(), points.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => return Vec::new(),
+    };
+
+    match kind {
+        ShapeKind::Freehand => vec![ShapePath::open(points.to_vec())],
+        ShapeKind::Segment => vec![ShapePath::open(vec![first, last])],
+        ShapeKind::Arrow => {
+            let mut paths = vec![ShapePath::open(vec![first, last])];
+
+            if let Some(head) = arrow_head(first, last, width) {
+                paths.push(ShapePath::open(head));
+            }
+
+            paths
+        }
+        ShapeKind::Rectangle => {
+            let rect = Rect::from_two_pos(first, last);
+
+            vec![ShapePath::closed(vec![
+                rect.left_top(),
+                rect.right_top(),
+                rect.right_bottom(),
+                rect.left_bottom(),
+            ])]
+        }
+        ShapeKind::Ellipse => vec![ShapePath::closed(ellipse_points(Rect::from_two_pos(
+            first, last,
+        )))],
+        ShapeKind::Polygon { .. } => vec![ShapePath::closed(points.to_vec())],
+        ShapeKind::Text(_) => Vec::new(),
+    }
+}
+
+/// The two barbs of an arrow pointing from `start` to `end`, as one polyline through `end`
+fn arrow_head(start: Pos2, end: Pos2, width: f32) -> Option<Vec<Pos2>> {
+    let shaft = end - start;
+
+    let length = shaft.length();
+
+    if length <= 0.0 {
+        return None;
+    }
+
+    // grows with the width, but never longer than half the shaft
+    let head_length = (4.0 * width + 8.0).min(length / 2.0);
+
+    let back = -shaft / length * head_length;
+
+    let barb = |angle: f32| end + Vec2::angled(back.angle() + angle) * head_length;
+
+    Some(vec![
+        barb(std::f32::consts::FRAC_PI_6),
+        end,
+        barb(-std::f32::consts::FRAC_PI_6),
+    ])
+}
+
+fn ellipse_points(rect: Rect) -> Vec<Pos2> {
+    let radius = rect.size() / 2.0;
+
+    (0..ELLIPSE_SEGMENTS)
+        .map(|i| {
+            let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+
+            rect.center() + Vec2::new(radius.x * angle.cos(), radius.y * angle.sin())
+        })
+        .collect()
+}
+
+/// The two edges of a stroke along `points` that is `widths[i]` wide at each point.
+/// Filling the left edge followed by the reversed right edge paints the stroke.
+pub fn stroke_sides(points: &[Pos2], widths: &[f32]) -> (Vec<Pos2>, Vec<Pos2>) {
+    let last = points.len().saturating_sub(1);
+
+    points
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(i, (point, width))| {
+            // perpendicular to the direction between the neighbours, which bisects the corner
+            let direction = points[(i + 1).min(last)] - points[i.saturating_sub(1)];
+
+            let normal = direction.normalized().rot90() * *width / 2.0;
+
+            (*point - normal, *point + normal)
+        })
+        .unzip()
+}
+
+pub fn distance_to_segment(point: Pos2, (start, end): (Pos2, Pos2)) -> f32 {
+    let segment = end - start;
+
+    let length_sq = segment.length_sq();
+
+    if length_sq <= 0.0 {
+        return point.distance(start);
+    }
+
+    let t = ((point - start).dot(segment) / length_sq).clamp(0.0, 1.0);
+
+    point.distance(start + segment * t)
+}
+
+/// Pieces of the polyline `points` that lie outside the circle around `center`
+pub fn cut_polyline(points: &[Pos2], center: Pos2, radius: f32) -> Vec<Vec<Pos2>> {
+    if let [point] = points {
+        return match point.distance(center) > radius {
+            true => vec![points.to_vec()],
+            false => Vec::new(),
+        };
+    }
+
+    let mut pieces = Vec::new();
+    let mut piece: Vec<Pos2> = Vec::new();
+
+    for segment in points.windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+
+        match circle_interval((start, end), center, radius) {
+            None => {
+                if piece.is_empty() {
+                    piece.push(start);
+                }
+
+                piece.push(end);
+            }
+            Some((enter, leave)) => {
+                if enter > 0.0 {
+                    if piece.is_empty() {
+                        piece.push(start);
+                    }
+
+                    piece.push(start.lerp(end, enter));
+                }
+
+                if !piece.is_empty() {
+                    pieces.push(std::mem::take(&mut piece));
+                }
+
+                if leave < 1.0 {
+                    piece = vec![start.lerp(end, leave), end];
+                }
+            }
+        }
+    }
+
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+
+    pieces.retain(|piece| piece.len() >= 2);
+
+    pieces
+}
+
+/// Pieces of `path` that lie outside the circle around `center`. A closed path stays in one piece if it is cut once.
+pub fn cut_path(path: &ShapePath, center: Pos2, radius: f32) -> Vec<Vec<Pos2>> {
+    let mut points = path.points.clone();
+
+    if path.closed {
+        if let Some(first) = points.first().copied() {
+            points.push(first);
+        }
+    }
+
+    let mut pieces = cut_polyline(&points, center, radius);
+
+    // the closing point is kept, so the last piece continues with the first one
+    if path.closed && pieces.len() >= 2 && pieces[0][0] == points[0] {
+        let first = pieces.remove(0);
+
+        if let Some(last) = pieces.last_mut() {
+            last.extend(first.into_iter().skip(1));
+        }
+    }
+
+    pieces
+}
+
+/// Part of the segment inside the circle, as the range of the segment parameter from 0 to 1
+fn circle_interval((start, end): (Pos2, Pos2), center: Pos2, radius: f32) -> Option<(f32, f32)> {
+    let direction = end - start;
+    let offset = start - center;
+
+    let a = direction.length_sq();
+    let b = 2.0 * offset.dot(direction);
+    let c = offset.length_sq() - radius * radius;
+
+    if a <= 0.0 {
+        return (c <= 0.0).then_some((0.0, 1.0));
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+
+    if discriminant <= 0.0 {
+        return None;
+    }
+
+    let root = discriminant.sqrt();
+
+    let (enter, leave) = ((-b - root) / (2.0 * a), (-b + root) / (2.0 * a));
+
+    if leave <= 0.0 || enter >= 1.0 {
+        return None;
+    }
+
+    Some((enter.max(0.0), leave.min(1.0)))
+}
+
+/// Even-odd rule, like the fill of the exporters
+pub fn is_inside_polygon(point: Pos2, polygon: &[Pos2]) -> bool {
+    let mut inside = false;
+
+    let mut previous = match polygon.last() {
+        Some(last) => *last,
+        None => return false,
+    };
+
+    for current in polygon {
+        if (current.y > point.y) != (previous.y > point.y)
+            && point.x
+                < (previous.x - current.x) * (point.y - current.y) / (previous.y - current.y)
+                    + current.x
+        {
+            inside = !inside;
+        }
+
+        previous = *current;
+    }
+
+    inside
+}
+
+/// Splits a simple polygon into triangles by ear clipping. Returns indices into `polygon`.
+pub fn triangulate(polygon: &[Pos2]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+    let mut triangles = Vec::new();
+
+    if polygon.len() < 3 {
+        return triangles;
+    }
+
+    // twice the signed area, positive for clockwise polygons in screen coordinates
+    let orientation: f32 = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum();
+
+    let is_convex = |a: Pos2, b: Pos2, c: Pos2| (b - a).x * (c - b).y - (b - a).y * (c - b).x;
+
+    while remaining.len() > 3 {
+        let len = remaining.len();
+
+        let ear = (0..len).find(|&i| {
+            let [a, b, c] = [
+                remaining[(i + len - 1) % len],
+                remaining[i],
+                remaining[(i + 1) % len],
+            ];
+
+            let turn = is_convex(polygon[a], polygon[b], polygon[c]);
+
+            turn * orientation > 0.0
+                && remaining
+                    .iter()
+                    .filter(|&&other| other != a && other != b && other != c)
+                    .all(|&other| {
+                        !is_inside_polygon(polygon[other], &[polygon[a], polygon[b], polygon[c]])
+                    })
+        });
+
+        // degenerate polygons have no ear, the rest is filled as a fan
+        let i = ear.unwrap_or(0);
+
+        triangles.push([
+            remaining[(i + len - 1) % len],
+            remaining[i],
+            remaining[(i + 1) % len],
+        ]);
+
+        remaining.remove(i);
+    }
+
+    if let [a, b, c] = remaining[..] {
+        triangles.push([a, b, c]);
+    }
+
+    triangles
+}
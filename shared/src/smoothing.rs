@@ -0,0 +1,150 @@
+//! Smoothing and simplification of freehand strokes, applied once a stroke is finished.
+
+use egui::{Pos2, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::shape::distance_to_segment;
+
+/// Points a Catmull-Rom spline places between two coordinates
+pub const CATMULL_ROM_SEGMENTS: usize = 4;
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Smoothing {
+    #[default]
+    None,
+    /// Every point becomes the mean of its neighbours
+    MovingAverage,
+    /// A curve through the coordinates
+    CatmullRom,
+}
+
+/// What happens to a freehand stroke when it is finished
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct StrokeFilter {
+    pub smoothing: Smoothing,
+    /// Points averaged by [`Smoothing::MovingAverage`]
+    pub window: usize,
+    /// Largest distance a point may have from the simplified stroke, 0 keeps every point
+    pub tolerance: f32,
+}
+
+impl Default for StrokeFilter {
+    fn default() -> Self {
+        Self {
+            smoothing: Smoothing::None,
+            window: 3,
+            tolerance: 0.0,
+        }
+    }
+}
+
+impl StrokeFilter {
+    /// Smooths `points`, then simplifies them
+    pub fn apply(&self, points: &[Pos2]) -> Vec<Pos2> {
+        let smoothed = match self.smoothing {
+            Smoothing::None => points.to_vec(),
+            Smoothing::MovingAverage => moving_average(points, self.window),
+            Smoothing::CatmullRom => catmull_rom(points, CATMULL_ROM_SEGMENTS),
+        };
+
+        simplify(&smoothed, self.tolerance)
+    }
+}
+
+/// Replaces every point with the mean of the `window` points around it.
+/// The window shrinks towards the ends, so the stroke keeps its first and last point.
+pub fn moving_average(points: &[Pos2], window: usize) -> Vec<Pos2> {
+    let half = window / 2;
+
+    (0..points.len())
+        .map(|i| {
+            let radius = half.min(i).min(points.len() - 1 - i);
+
+            let neighbours = &points[i - radius..=i + radius];
+
+            let sum = neighbours
+                .iter()
+                .fold(Vec2::ZERO, |sum, point| sum + point.to_vec2());
+
+            (sum / neighbours.len() as f32).to_pos2()
+        })
+        .collect()
+}
+
+/// Uniform Catmull-Rom spline through `points` with `segments` points per span
+pub fn catmull_rom(points: &[Pos2], segments: usize) -> Vec<Pos2> {
+    if points.len() < 3 || segments < 2 {
+        return points.to_vec();
+    }
+
+    let last = points.len() - 1;
+
+    let mut curve = Vec::with_capacity(last * segments + 1);
+
+    for i in 0..last {
+        // the ends are repeated, so the curve starts and ends on the stroke
+        let [p0, p1, p2, p3] = [
+            points[i.saturating_sub(1)],
+            points[i],
+            points[i + 1],
+            points[(i + 2).min(last)],
+        ]
+        .map(|p| p.to_vec2());
+
+        for segment in 0..segments {
+            let t = segment as f32 / segments as f32;
+
+            let point = 0.5
+                * (2.0 * p1
+                    + (p2 - p0) * t
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+                    + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t * t);
+
+            curve.push(point.to_pos2());
+        }
+    }
+
+    curve.push(points[last]);
+
+    curve
+}
+
+/// Ramer–Douglas–Peucker: drops points that are closer than `tolerance` to the simplified stroke
+pub fn simplify(points: &[Pos2], tolerance: f32) -> Vec<Pos2> {
+    if points.len() < 3 || tolerance <= 0.0 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut ranges = vec![(0, points.len() - 1)];
+
+    while let Some((start, end)) = ranges.pop() {
+        let farthest = (start + 1..end)
+            .map(|i| {
+                (
+                    i,
+                    distance_to_segment(points[i], (points[start], points[end])),
+                )
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        if let Some((i, distance)) = farthest {
+            if distance > tolerance {
+                keep[i] = true;
+                ranges.push((start, i));
+                ranges.push((i, end));
+            }
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(point, keep)| keep.then_some(*point))
+        .collect()
+}
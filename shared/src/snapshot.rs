@@ -0,0 +1,131 @@
+//! Board snapshots, i.e. a board saved to a file.
+//!
+//! A snapshot is a JSON object:
+//!
+//! ```json
+//! {
+//!   "format": "synced-drawing-board",
+//!   "version": 1,
+//!   "metadata": { "title": "Synced Drawing", "created": 1697500000, "line_count": 1 },
+//!   "background": "map",
+//!   "lines": {
+//!     "schema": 6,
+//!     "lines": {
+//!       "42": {
+//!         "coordinates": [[1.0, 2.0], [3.0, 4.0]],
+//!         "stroke": { "color": [255, 0, 0, 255], "width": 5.0 },
+//!         "style": { "pattern": "solid", "opacity": 1.0, "cap": "butt", "highlighter": false },
+//!         "shape": { "kind": "freehand" },
+//!         "pressure": [],
+//!         "flag": null
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! - `format` is always [`SNAPSHOT_FORMAT`]
+//! - `version` is [`SNAPSHOT_VERSION`] at the time of writing. Newer versions are rejected.
+//! - `metadata.created` is in seconds since the Unix epoch
+//! - `background` is the name of the map image without extension, or `null`
+//! - `lines` are versioned as described in [`crate::schema`], older versions are migrated when loading
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{Line, Lines};
+
+pub const SNAPSHOT_FORMAT: &str = "synced-drawing-board";
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct SnapshotMetadata {
+    pub title: String,
+    pub created: u64,
+    pub line_count: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Snapshot {
+    pub format: String,
+    pub version: u32,
+    pub metadata: SnapshotMetadata,
+    pub background: Option<String>,
+    pub lines: Lines,
+}
+
+/// What happens to the lines already on the board when a snapshot is loaded
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LoadMode {
+    #[default]
+    Replace,
+    Merge,
+}
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("failed to parse snapshot: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("not a board snapshot, format is {0:?}")]
+    UnknownFormat(String),
+    #[error("snapshot version {0} is newer than the supported version {SNAPSHOT_VERSION}")]
+    UnsupportedVersion(u32),
+}
+
+impl Snapshot {
+    pub fn new(title: String, created: u64, background: Option<String>, lines: Lines) -> Self {
+        Self {
+            format: SNAPSHOT_FORMAT.to_string(),
+            version: SNAPSHOT_VERSION,
+            metadata: SnapshotMetadata {
+                title,
+                created,
+                line_count: lines.len(),
+            },
+            background,
+            lines,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        let snapshot: Snapshot = serde_json::from_str(json)?;
+
+        if snapshot.format != SNAPSHOT_FORMAT {
+            return Err(SnapshotError::UnknownFormat(snapshot.format));
+        }
+
+        if snapshot.version > SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(snapshot.version));
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Puts the lines of the snapshot on the board. When merging, lines whose id is already taken get a new id.
+    /// Returns the ids of the added lines.
+    pub fn apply(self, lines: &mut Lines, mode: LoadMode) -> Vec<usize> {
+        if mode == LoadMode::Replace {
+            lines.clear();
+        }
+
+        self.lines
+            .0
+            .into_iter()
+            .map(|(mut line_id, line): (usize, Line)| {
+                while lines.contains_key(&line_id) {
+                    line_id = rand::random();
+                }
+
+                lines.0.insert(line_id, line);
+
+                line_id
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,185 @@
+use egui::Pos2;
+use serde::{Deserialize, Serialize};
+
+use crate::color::Color;
+
+/// Highlighter strokes are painted with this share of their opacity, beneath all other strokes
+pub const HIGHLIGHTER_OPACITY: f32 = 0.5;
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Pattern {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+/// Shape of the ends of a stroke. Square and round caps stick out by half the width.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Cap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+/// How a line is painted apart from its color and width
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct StrokeStyle {
+    pub pattern: Pattern,
+    /// From 0 to 1, multiplied with the alpha of the color
+    pub opacity: f32,
+    pub cap: Cap,
+    pub highlighter: bool,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self {
+            pattern: Pattern::Solid,
+            opacity: 1.0,
+            cap: Cap::Butt,
+            highlighter: false,
+        }
+    }
+}
+
+impl StrokeStyle {
+    /// Color the stroke is painted with, i.e. with opacity and highlighter applied
+    pub fn paint_color(&self, color: Color) -> Color {
+        let opacity = match self.highlighter {
+            true => self.opacity * HIGHLIGHTER_OPACITY,
+            false => self.opacity,
+        };
+
+        Color {
+            a: (color.a as f32 * opacity.clamp(0.0, 1.0)).round() as u8,
+            ..color
+        }
+    }
+
+    /// Cap every dash is painted with. Dots are always round.
+    pub fn paint_cap(&self) -> Cap {
+        match self.pattern {
+            Pattern::Dotted => Cap::Round,
+            _ => self.cap,
+        }
+    }
+
+    /// Dash and gap length in canvas units for a stroke of `width`, `None` for solid strokes.
+    ///
+    /// Like SVG dash arrays the lengths don't include the caps, so dashes look the same with every cap:
+    /// dashes are 3 widths long with a gap of 2 widths, dots are 1 width wide with a gap of 1 width.
+    pub fn dash_array(&self, width: f32) -> Option<[f32; 2]> {
+        let [dash, gap] = self.pattern_array(width)?;
+
+        let caps = match self.paint_cap() {
+            Cap::Butt => 0.0,
+            Cap::Round | Cap::Square => width,
+        };
+
+        Some([(dash - caps).max(0.0), gap + caps])
+    }
+
+    /// Dash and gap length in canvas units including the caps, for strokes that are painted without caps.
+    pub fn pattern_array(&self, width: f32) -> Option<[f32; 2]> {
+        match self.pattern {
+            Pattern::Solid => None,
+            Pattern::Dashed => Some([3.0 * width, 2.0 * width]),
+            Pattern::Dotted => Some([width, width]),
+        }
+    }
+}
+
+/// Splits a polyline into dashes of length `dash` that are `gap` apart. Dashes of length 0 are single points.
+pub fn dashes(points: &[Pos2], dash: f32, gap: f32) -> Vec<Vec<Pos2>> {
+    let widths = vec![0.0; points.len()];
+
+    width_dashes(points, &widths, dash, gap)
+        .into_iter()
+        .map(|(points, _)| points)
+        .collect()
+}
+
+/// Like [`dashes`] for a stroke that is `widths[i]` wide at `points[i]`, with the width at every point of the dashes
+pub fn width_dashes(
+    points: &[Pos2],
+    widths: &[f32],
+    dash: f32,
+    gap: f32,
+) -> Vec<(Vec<Pos2>, Vec<f32>)> {
+    let mut dashes = Vec::new();
+
+    if points.is_empty() || points.len() != widths.len() || dash + gap <= 0.0 {
+        return dashes;
+    }
+
+    let mut current = (vec![points[0]], vec![widths[0]]);
+    // distance left until the current dash or gap ends
+    let mut remaining = dash;
+    let mut is_dash = true;
+
+    for (segment, segment_widths) in points.windows(2).zip(widths.windows(2)) {
+        let (mut start, end) = (segment[0], segment[1]);
+        let (mut start_width, end_width) = (segment_widths[0], segment_widths[1]);
+
+        let mut length = start.distance(end);
+
+        while remaining <= length {
+            let t = remaining / length.max(f32::EPSILON);
+
+            let position = start + (end - start) * t;
+            let width = start_width + (end_width - start_width) * t;
+
+            match is_dash {
+                true => {
+                    current.0.push(position);
+                    current.1.push(width);
+                    dashes.push(std::mem::take(&mut current));
+                }
+                false => current = (vec![position], vec![width]),
+            }
+
+            length -= remaining;
+            start = position;
+            start_width = width;
+            is_dash = !is_dash;
+            remaining = if is_dash { dash } else { gap };
+        }
+
+        remaining -= length;
+
+        if is_dash {
+            current.0.push(end);
+            current.1.push(end_width);
+        }
+    }
+
+    if is_dash && !current.0.is_empty() {
+        dashes.push(current);
+    }
+
+    dashes
+}
+
+/// Moves both ends of a polyline outwards by `amount`, which is how square caps are painted
+pub fn extend_ends(points: &mut [Pos2], amount: f32) {
+    let len = points.len();
+
+    if len < 2 {
+        return;
+    }
+
+    let start_direction = (points[0] - points[1]).normalized();
+    let end_direction = (points[len - 1] - points[len - 2]).normalized();
+
+    if start_direction.is_finite() {
+        points[0] += start_direction * amount;
+    }
+
+    if end_direction.is_finite() {
+        points[len - 1] += end_direction * amount;
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::OnceLock;
+
+use ab_glyph::{Font, FontArc, GlyphId, OutlineCurve, ScaleFont};
+use egui::{Pos2, Rect, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::SPos2;
+
+pub const DEFAULT_FONT_SIZE: f32 = 24.0;
+
+/// The proportional font of egui, so that the server lays out text exactly like the frontend
+const FONT_NAME: &str = "Ubuntu-Light";
+
+/// A text label, painted with the stroke color of its line.
+/// Rows are separated by `\n`, the anchor is the top left corner of the first row.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Text {
+    pub anchor: SPos2,
+    pub content: String,
+    /// In canvas units, so that text scales with the map
+    pub font_size: f32,
+}
+
+/// A glyph of a laid out [`Text`]
+#[derive(Debug, Clone, Copy)]
+pub struct PositionedGlyph {
+    pub id: GlyphId,
+    /// Where the baseline starts, in canvas coordinates
+    pub origin: Pos2,
+}
+
+/// A piece of a glyph outline in canvas coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    Line(Pos2, Pos2),
+    Quad(Pos2, Pos2, Pos2),
+    Cubic(Pos2, Pos2, Pos2, Pos2),
+}
+
+impl Curve {
+    pub fn start(&self) -> Pos2 {
+        match self {
+            Curve::Line(start, _) | Curve::Quad(start, ..) | Curve::Cubic(start, ..) => *start,
+        }
+    }
+}
+
+pub fn font() -> &'static FontArc {
+    static FONT: OnceLock<FontArc> = OnceLock::new();
+
+    FONT.get_or_init(|| {
+        let definitions = egui::FontDefinitions::default();
+
+        let data = definitions
+            .font_data
+            .get(FONT_NAME)
+            .expect("egui is built with its default fonts");
+
+        FontArc::try_from_vec(data.font.to_vec()).expect("default font of egui is valid")
+    })
+}
+
+impl Text {
+    pub fn new(anchor: SPos2, font_size: f32) -> Self {
+        Self {
+            anchor,
+            content: String::new(),
+            font_size,
+        }
+    }
+
+    /// Distance between the top of a row and its baseline
+    pub fn ascent(&self) -> f32 {
+        font().as_scaled(self.font_size).ascent()
+    }
+
+    /// Distance between the tops of two rows
+    pub fn row_height(&self) -> f32 {
+        let font = font().as_scaled(self.font_size);
+
+        font.ascent() - font.descent() + font.line_gap()
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &str> {
+        self.content.split('\n')
+    }
+
+    /// Positions of all glyphs, with kerning
+    pub fn glyphs(&self) -> Vec<PositionedGlyph> {
+        self.rows()
+            .enumerate()
+            .flat_map(|(row, content)| self.layout_row(row, content).0)
+            .collect()
+    }
+
+    /// Area covered by the rows
+    pub fn rect(&self) -> Rect {
+        let width = self
+            .rows()
+            .enumerate()
+            .map(|(row, content)| self.layout_row(row, content).1)
+            .fold(0.0, f32::max);
+
+        let height = self.rows().count() as f32 * self.row_height();
+
+        Rect::from_min_size(self.anchor.0, Vec2::new(width, height))
+    }
+
+    /// Glyphs of a row and its width
+    fn layout_row(&self, row: usize, content: &str) -> (Vec<PositionedGlyph>, f32) {
+        let font = font().as_scaled(self.font_size);
+
+        let start = self.anchor.0 + Vec2::new(0.0, row as f32 * self.row_height() + self.ascent());
+
+        let mut origin = start;
+        let mut previous: Option<GlyphId> = None;
+        let mut glyphs = Vec::new();
+
+        for c in content.chars() {
+            let id = font.glyph_id(c);
+
+            if let Some(previous) = previous {
+                origin.x += font.kern(previous, id);
+            }
+
+            glyphs.push(PositionedGlyph { id, origin });
+
+            origin.x += font.h_advance(id);
+            previous = Some(id);
+        }
+
+        (glyphs, origin.x - start.x)
+    }
+
+    /// Outlines of all glyphs, to be filled with the non-zero rule.
+    /// A new contour starts wherever a curve does not start at the end of the previous one.
+    pub fn outline(&self) -> Vec<Curve> {
+        let font = font().as_scaled(self.font_size);
+
+        let (scale_x, scale_y) = (font.h_scale_factor(), font.v_scale_factor());
+
+        self.glyphs()
+            .into_iter()
+            .filter_map(|glyph| Some((glyph.origin, font.font.outline(glyph.id)?)))
+            .flat_map(|(origin, outline)| {
+                // font units point up
+                let to_canvas =
+                    move |p: ab_glyph::Point| origin + Vec2::new(p.x * scale_x, -p.y * scale_y);
+
+                outline.curves.into_iter().map(move |curve| match curve {
+                    OutlineCurve::Line(a, b) => Curve::Line(to_canvas(a), to_canvas(b)),
+                    OutlineCurve::Quad(a, b, c) => {
+                        Curve::Quad(to_canvas(a), to_canvas(b), to_canvas(c))
+                    }
+                    OutlineCurve::Cubic(a, b, c, d) => {
+                        Curve::Cubic(to_canvas(a), to_canvas(b), to_canvas(c), to_canvas(d))
+                    }
+                })
+            })
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.content.trim().is_empty()
+    }
+}
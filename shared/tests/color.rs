@@ -0,0 +1,114 @@
+use serde_json::json;
+use shared::{
+    color::{Color, ParseColorError},
+    StrokeX,
+};
+
+/// Every value of every channel, each combined with every alpha
+fn colors() -> impl Iterator<Item = Color> {
+    (0..=255u8).flat_map(|value| {
+        (0..=255u8).flat_map(move |a| {
+            [
+                Color::from_rgba_unmultiplied(value, 0, 0, a),
+                Color::from_rgba_unmultiplied(0, value, 0, a),
+                Color::from_rgba_unmultiplied(0, 0, value, a),
+                Color::from_rgba_unmultiplied(value, 255 - value, value / 2, a),
+            ]
+        })
+    })
+}
+
+#[test]
+fn json_round_trip() {
+    for color in colors() {
+        let json = serde_json::to_value(color).unwrap();
+
+        assert_eq!(json, json!([color.r, color.g, color.b, color.a]));
+        assert_eq!(serde_json::from_value::<Color>(json).unwrap(), color);
+    }
+}
+
+#[test]
+fn hex_round_trip() {
+    for color in colors() {
+        let hex = color.to_hex();
+
+        assert_eq!(hex.parse::<Color>().unwrap(), color);
+        assert_eq!(serde_json::from_value::<Color>(json!(hex)).unwrap(), color);
+    }
+}
+
+#[test]
+fn stroke_round_trip() {
+    for color in colors() {
+        let stroke = StrokeX::new(2.5, color);
+
+        let json = serde_json::to_string(&stroke).unwrap();
+
+        assert_eq!(serde_json::from_str::<StrokeX>(&json).unwrap(), stroke);
+    }
+}
+
+#[test]
+fn hex_forms() {
+    let cases = [
+        ("#f00", Color::from_rgb(255, 0, 0)),
+        ("#f008", Color::from_rgba_unmultiplied(255, 0, 0, 0x88)),
+        ("#12ab9F", Color::from_rgb(0x12, 0xab, 0x9f)),
+        (
+            "#12ab9f00",
+            Color::from_rgba_unmultiplied(0x12, 0xab, 0x9f, 0),
+        ),
+        ("12ab9f", Color::from_rgb(0x12, 0xab, 0x9f)),
+        (" #000 ", Color::BLACK),
+    ];
+
+    for (hex, color) in cases {
+        assert_eq!(hex.parse::<Color>(), Ok(color), "{}", hex);
+    }
+}
+
+#[test]
+fn invalid_hex() {
+    for hex in [
+        "",
+        "#",
+        "#ff",
+        "#ff000",
+        "#ff0000000",
+        "#gg0000",
+        "#+f0",
+        "#+f0000",
+        "#ff\u{e9}0",
+        "red",
+    ] {
+        assert_eq!(
+            hex.parse::<Color>(),
+            Err(ParseColorError(hex.to_string())),
+            "{}",
+            hex
+        );
+    }
+}
+
+#[test]
+fn json_forms() {
+    assert_eq!(
+        serde_json::from_value::<Color>(json!([1, 2, 3])).unwrap(),
+        Color::from_rgb(1, 2, 3)
+    );
+
+    for invalid in [
+        json!([256, 0, 0, 0]),
+        json!([0, 0]),
+        json!([0, 0, 0, 0, 0]),
+        json!("#xyz"),
+        json!(null),
+    ] {
+        assert!(
+            serde_json::from_value::<Color>(invalid.clone()).is_err(),
+            "{}",
+            invalid
+        );
+    }
+}
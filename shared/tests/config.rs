@@ -0,0 +1,134 @@
+use std::path::PathBuf;
+
+use shared::config::{Config, ConfigError};
+
+/// Writes `content` to a file of its own in the temporary directory
+fn config_file(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "synced-drawing-{}-{}.toml",
+        name,
+        std::process::id()
+    ));
+
+    std::fs::write(&path, content).unwrap();
+
+    path
+}
+
+/// Makes the default configuration invalid
+type Change = fn(&mut Config);
+
+fn invalid_key(config: &Config) -> Option<String> {
+    match config.validate() {
+        Err(ConfigError::InvalidValue { key, .. }) => Some(key),
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(()) => None,
+    }
+}
+
+#[test]
+fn missing_files_are_reported() {
+    let path = std::env::temp_dir().join("synced-drawing-does-not-exist.toml");
+
+    assert!(matches!(
+        Config::load(&path),
+        Err(ConfigError::MissingFile(missing)) if missing == path
+    ));
+}
+
+#[test]
+fn parse_errors_point_at_the_line_and_column() {
+    let path = config_file(
+        "parse-error",
+        "[host]\nport = 8000\nbind = localhost\n\n[website]\ntitle = \"Maps\"\n",
+    );
+
+    let result = Config::load(&path);
+
+    std::fs::remove_file(&path).unwrap();
+
+    match result {
+        Err(ConfigError::Parse {
+            path: error_path,
+            line,
+            column,
+            ..
+        }) => {
+            assert_eq!(error_path, path);
+            assert_eq!((line, column), (3, 8));
+        }
+        other => panic!("Expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn wrong_types_point_at_the_line() {
+    let path = config_file("wrong-type", "[limits]\nmax_lines = \"many\"\n");
+
+    let result = Config::load(&path);
+
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(ConfigError::Parse { line: 2, .. })));
+}
+
+#[test]
+fn loading_does_not_validate() {
+    // fixed by a command-line flag before the configuration is validated
+    let path = config_file("unvalidated", "[server]\nlog_level = \"loud\"\n");
+
+    let result = Config::load(&path);
+
+    std::fs::remove_file(&path).unwrap();
+
+    let mut config = result.unwrap();
+
+    assert_eq!(invalid_key(&config).as_deref(), Some("server.log_level"));
+
+    config.server.log_level = "warn".to_string();
+
+    assert_eq!(invalid_key(&config), None);
+}
+
+#[test]
+fn defaults_are_valid() {
+    assert_eq!(invalid_key(&Config::default()), None);
+}
+
+#[test]
+fn invalid_values_name_their_key() {
+    let cases: [(&str, Change); 7] = [
+        ("host.port", |config| config.host.port = 0),
+        ("host.bind", |config| config.host.bind = " ".to_string()),
+        ("server.log_level", |config| {
+            config.server.log_level = "verbose".to_string()
+        }),
+        ("cors.allow_origin", |config| {
+            config.cors.allow_origin = String::new()
+        }),
+        ("cors.allow_origin", |config| {
+            config.cors.allow_origin = "https://maps\n".to_string()
+        }),
+        ("limits.max_body_size", |config| {
+            config.limits.max_body_size = 0
+        }),
+        ("limits.max_lines", |config| config.limits.max_lines = 0),
+    ];
+
+    for (key, change) in cases {
+        let mut config = Config::default();
+
+        change(&mut config);
+
+        assert_eq!(invalid_key(&config).as_deref(), Some(key));
+    }
+}
+
+#[test]
+fn log_levels_are_case_insensitive() {
+    let mut config = Config::default();
+
+    config.server.log_level = "WARN".to_string();
+
+    assert_eq!(invalid_key(&config), None);
+}
@@ -0,0 +1,51 @@
+use egui::{pos2, Rect};
+use shared::grid::{Grid, GridKind, Snap};
+
+fn grid(kind: GridKind) -> Grid {
+    Grid {
+        kind,
+        cell_size: 10.0,
+        offset: [5.0, 0.0],
+        ..Grid::default()
+    }
+}
+
+#[test]
+fn square_grid_snaps_to_corners_and_centers() {
+    let square = grid(GridKind::Square);
+
+    assert_eq!(
+        square.snap(pos2(13.0, 4.0), Snap::Intersections),
+        pos2(15.0, 0.0)
+    );
+    assert_eq!(square.snap(pos2(13.0, 4.0), Snap::Centers), pos2(10.0, 5.0));
+    assert_eq!(square.snap(pos2(13.0, 4.0), Snap::None), pos2(13.0, 4.0));
+}
+
+#[test]
+fn hex_grid_snaps_to_the_cell_the_point_is_in() {
+    let hex = grid(GridKind::Hex);
+
+    // right below the corner between the cells at 5 and 15 in the first row
+    let center = hex.snap(pos2(10.0, 5.0), Snap::Centers);
+
+    assert!(center.distance(pos2(10.0, 3f32.sqrt() * 5.0)) < 1e-4);
+
+    let corner = hex.snap(pos2(10.5, 0.5), Snap::Intersections);
+
+    assert!(corner.distance(pos2(10.0, 10.0 / 3f32.sqrt() / 2.0)) < 1e-4);
+}
+
+#[test]
+fn dense_grids_are_not_drawn() {
+    let square = grid(GridKind::Square);
+
+    let paths = square.paths(Rect::from_min_max(pos2(0.0, 0.0), pos2(30.0, 20.0)));
+
+    // 3 columns at 5, 15 and 25, 3 rows at 0, 10 and 20
+    assert_eq!(paths.len(), 6);
+
+    assert!(square
+        .paths(Rect::from_min_max(pos2(0.0, 0.0), pos2(1e6, 1e6)))
+        .is_empty());
+}
@@ -0,0 +1,25 @@
+use egui::pos2;
+use shared::measure::{path_length, polygon_area, Calibration};
+
+#[test]
+fn lengths_and_areas_use_the_calibration() {
+    let square = [
+        pos2(0.0, 0.0),
+        pos2(0.0, 20.0),
+        pos2(20.0, 20.0),
+        pos2(20.0, 0.0),
+    ];
+
+    assert_eq!(path_length(&square), 60.0);
+    assert_eq!(polygon_area(&square), 400.0);
+
+    // the first side is known to be 4 meters long
+    let calibration = Calibration::from_measurement(path_length(&square[..2]), 4.0, " m ").unwrap();
+
+    assert_eq!(calibration.unit, "m");
+    assert_eq!(calibration.format_length(60.0), "12.00 m");
+    assert_eq!(calibration.format_area(400.0), "16.00 m²");
+
+    assert!(Calibration::from_measurement(0.0, 4.0, "m").is_none());
+    assert!(Calibration::from_measurement(20.0, 0.0, "m").is_none());
+}
@@ -0,0 +1,98 @@
+//! Round trips of the serialized lines, pinned to the fixtures in `tests/fixtures`.
+//! `lines_v<n>.json` holds the same lines written with schema version `n`.
+
+use std::path::Path;
+
+use serde_json::{json, Value};
+use shared::{
+    schema::{migrate_line, SchemaError, SCHEMA_VERSION},
+    Lines, Message,
+};
+
+fn fixture(version: u32) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(format!("lines_v{}.json", version));
+
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Missing fixture {}: {}", path.display(), e))
+}
+
+fn fixture_value(version: u32) -> Value {
+    serde_json::from_str(&fixture(version)).unwrap()
+}
+
+#[test]
+fn current_version_round_trips() {
+    let lines: Lines = serde_json::from_str(&fixture(SCHEMA_VERSION)).unwrap();
+
+    assert_eq!(lines.len(), 3);
+
+    assert_eq!(
+        serde_json::to_value(&lines).unwrap(),
+        fixture_value(SCHEMA_VERSION)
+    );
+}
+
+#[test]
+fn every_version_migrates_to_current_fixture() {
+    for version in 0..=SCHEMA_VERSION {
+        let lines: Lines = serde_json::from_str(&fixture(version))
+            .unwrap_or_else(|e| panic!("Failed to read version {}: {}", version, e));
+
+        assert_eq!(
+            serde_json::to_value(&lines).unwrap(),
+            fixture_value(SCHEMA_VERSION),
+            "version {} does not migrate to the current fixture",
+            version
+        );
+    }
+}
+
+#[test]
+fn serialized_lines_are_stable() {
+    let lines: Lines = serde_json::from_str(&fixture(SCHEMA_VERSION)).unwrap();
+
+    let json = serde_json::to_value(&lines).unwrap();
+
+    let lines: Lines = serde_json::from_value(json.clone()).unwrap();
+
+    assert_eq!(serde_json::to_value(&lines).unwrap(), json);
+}
+
+#[test]
+fn newer_version_is_rejected() {
+    let payload = json!({ "schema": SCHEMA_VERSION + 1, "lines": {} });
+
+    assert!(serde_json::from_value::<Lines>(payload).is_err());
+
+    assert!(matches!(
+        migrate_line(json!({}), SCHEMA_VERSION + 1),
+        Err(SchemaError::UnsupportedVersion(_))
+    ));
+}
+
+#[test]
+fn invalid_old_line_is_rejected() {
+    let line = json!({ "coordinates": [], "stroke": [255, 0, 0], "flag": null });
+
+    assert!(matches!(
+        migrate_line(line, 1),
+        Err(SchemaError::InvalidLine { version: 1, .. })
+    ));
+}
+
+#[test]
+fn message_from_unversioned_client_is_read() {
+    let message = format!(
+        r#"{{ "lines": {}, "changed_lines": null, "flag": null }}"#,
+        fixture(0)
+    );
+
+    let message: Message = serde_json::from_str(&message).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&message.lines).unwrap(),
+        fixture_value(SCHEMA_VERSION)
+    );
+}
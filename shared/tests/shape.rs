@@ -0,0 +1,220 @@
+use egui::{pos2, Pos2, Vec2};
+use shared::{
+    color::Color,
+    shape::{stroke_sides, triangulate, ShapeKind},
+    style::Pattern,
+    Line, SPos2, StrokeX,
+};
+
+fn line(shape: ShapeKind, points: &[Pos2]) -> Line {
+    Line {
+        coordinates: points.iter().map(|p| SPos2(*p)).collect(),
+        stroke: StrokeX::new(2.0, Color::BLACK),
+        shape,
+        ..Line::new()
+    }
+}
+
+fn area(points: &[Pos2]) -> f32 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<f32>()
+        .abs()
+        / 2.0
+}
+
+#[test]
+fn two_point_shapes_use_first_and_last_coordinate() {
+    let points = [pos2(0.0, 0.0), pos2(5.0, 5.0), pos2(10.0, 20.0)];
+
+    let segment = line(ShapeKind::Segment, &points).paths();
+    assert_eq!(segment.len(), 1);
+    assert_eq!(segment[0].points, vec![points[0], points[2]]);
+
+    let arrow = line(ShapeKind::Arrow, &points).paths();
+    assert_eq!(arrow.len(), 2);
+    assert_eq!(arrow[1].points[1], points[2]);
+
+    let rectangle = line(ShapeKind::Rectangle, &points).paths();
+    assert!(rectangle[0].closed);
+    assert_eq!(area(&rectangle[0].points), 200.0);
+}
+
+#[test]
+fn rectangle_is_hit_on_its_edges_only() {
+    let rectangle = line(ShapeKind::Rectangle, &[pos2(0.0, 0.0), pos2(100.0, 50.0)]);
+
+    assert!(rectangle.hit_test(pos2(50.0, 0.0), 1.0));
+    assert!(rectangle.hit_test(pos2(101.5, 25.0), 1.0));
+    assert!(!rectangle.hit_test(pos2(50.0, 25.0), 1.0));
+}
+
+#[test]
+fn filled_polygon_is_hit_inside() {
+    let points = [pos2(0.0, 0.0), pos2(100.0, 0.0), pos2(50.0, 100.0)];
+
+    let outline = line(ShapeKind::Polygon { fill: None }, &points);
+    let filled = line(
+        ShapeKind::Polygon {
+            fill: Some(Color::RED),
+        },
+        &points,
+    );
+
+    assert!(!outline.hit_test(pos2(50.0, 40.0), 1.0));
+    assert!(filled.hit_test(pos2(50.0, 40.0), 1.0));
+    assert!(!filled.hit_test(pos2(90.0, 90.0), 1.0));
+}
+
+#[test]
+fn ellipse_fits_into_its_rectangle() {
+    let ellipse = line(ShapeKind::Ellipse, &[pos2(10.0, 10.0), pos2(50.0, 30.0)]);
+
+    let rect = ellipse.bounding_rect();
+
+    assert_eq!(rect.min, pos2(9.0, 9.0));
+    assert_eq!(rect.max, pos2(51.0, 31.0));
+}
+
+#[test]
+fn concave_polygon_is_triangulated() {
+    // an arrow pointing right, concave at (40, 50)
+    let polygon = [
+        pos2(0.0, 0.0),
+        pos2(100.0, 50.0),
+        pos2(0.0, 100.0),
+        pos2(40.0, 50.0),
+    ];
+
+    for polygon in [polygon.to_vec(), polygon.iter().rev().copied().collect()] {
+        let triangles = triangulate(&polygon);
+
+        assert_eq!(triangles.len(), polygon.len() - 2);
+
+        let triangle_area: f32 = triangles
+            .iter()
+            .map(|[a, b, c]| area(&[polygon[*a], polygon[*b], polygon[*c]]))
+            .sum();
+
+        assert_eq!(triangle_area, area(&polygon));
+    }
+}
+
+#[test]
+fn lines_are_selected_when_completely_inside() {
+    let lasso = [
+        pos2(0.0, 0.0),
+        pos2(100.0, 0.0),
+        pos2(100.0, 100.0),
+        pos2(0.0, 100.0),
+    ];
+
+    let mut segment = line(ShapeKind::Segment, &[pos2(10.0, 10.0), pos2(90.0, 90.0)]);
+
+    assert!(segment.is_inside(&lasso));
+    assert!(!line(ShapeKind::Freehand, &[]).is_inside(&lasso));
+
+    segment.translate(Vec2::new(20.0, 0.0));
+
+    assert_eq!(segment.coordinates[1].0, pos2(110.0, 90.0));
+    assert!(!segment.is_inside(&lasso));
+}
+
+#[test]
+fn eraser_splits_strokes() {
+    let mut freehand = line(
+        ShapeKind::Freehand,
+        &[pos2(0.0, 0.0), pos2(50.0, 0.0), pos2(100.0, 0.0)],
+    );
+    freehand.stroke.width = 0.0;
+
+    assert!(freehand.erase(pos2(50.0, 20.0), 10.0).is_none());
+
+    let remainders = freehand.erase(pos2(50.0, 0.0), 10.0).unwrap();
+
+    assert_eq!(remainders.len(), 2);
+    assert_eq!(remainders[0].coordinates.last().unwrap().0, pos2(40.0, 0.0));
+    assert_eq!(remainders[1].coordinates[0].0, pos2(60.0, 0.0));
+    assert_eq!(
+        remainders[1].coordinates.last().unwrap().0,
+        pos2(100.0, 0.0)
+    );
+
+    // the eraser also cuts between coordinates
+    let segment = line(ShapeKind::Segment, &[pos2(0.0, 0.0), pos2(100.0, 0.0)]);
+
+    assert_eq!(segment.erase(pos2(50.0, 0.0), 5.0).unwrap().len(), 2);
+    assert!(segment.erase(pos2(0.0, 0.0), 200.0).unwrap().is_empty());
+}
+
+#[test]
+fn erased_closed_shapes_stay_connected() {
+    let rectangle = line(ShapeKind::Rectangle, &[pos2(0.0, 0.0), pos2(100.0, 50.0)]);
+
+    let remainders = rectangle.erase(pos2(50.0, 0.0), 5.0).unwrap();
+
+    assert_eq!(remainders.len(), 1);
+    assert_eq!(remainders[0].shape, ShapeKind::Freehand);
+    assert_eq!(remainders[0].coordinates.len(), 6);
+}
+
+#[test]
+fn pressure_scales_the_width() {
+    let mut freehand = line(
+        ShapeKind::Freehand,
+        &[pos2(0.0, 0.0), pos2(50.0, 0.0), pos2(100.0, 0.0)],
+    );
+
+    assert_eq!(freehand.widths(), None);
+
+    freehand.pressure = vec![0.5, 1.0, 0.25];
+
+    assert_eq!(freehand.widths(), Some(vec![1.0, 2.0, 0.5]));
+
+    let (left, right) = stroke_sides(&[pos2(0.0, 0.0), pos2(10.0, 0.0)], &[2.0, 4.0]);
+
+    assert_eq!(left, vec![pos2(0.0, 1.0), pos2(10.0, 2.0)]);
+    assert_eq!(right, vec![pos2(0.0, -1.0), pos2(10.0, -2.0)]);
+
+    // the remainders keep the pressure of their coordinates
+    freehand.stroke.width = 0.0;
+
+    let remainders = freehand.erase(pos2(75.0, 0.0), 5.0).unwrap();
+
+    assert_eq!(remainders[0].pressure, vec![0.5, 1.0, 1.0]);
+    assert_eq!(remainders[1].pressure, vec![0.25, 0.25]);
+}
+
+#[test]
+fn dashed_pressure_strokes_keep_their_dashes() {
+    let mut freehand = line(ShapeKind::Freehand, &[pos2(0.0, 0.0), pos2(20.0, 0.0)]);
+
+    freehand.pressure = vec![0.5, 1.5];
+
+    assert_eq!(freehand.pressure_parts().unwrap().len(), 1);
+
+    // dashes of 3 widths with gaps of 2 widths, including the caps
+    freehand.style.pattern = Pattern::Dashed;
+
+    let parts = freehand.pressure_parts().unwrap();
+
+    let starts: Vec<(Pos2, f32)> = parts
+        .iter()
+        .map(|(points, widths)| (points[0], widths[0]))
+        .collect();
+    let ends: Vec<(Pos2, f32)> = parts
+        .iter()
+        .map(|(points, widths)| (*points.last().unwrap(), *widths.last().unwrap()))
+        .collect();
+
+    let close = |(a, a_width): (Pos2, f32), (b, b_width): (Pos2, f32)| {
+        a.distance(b) < 1e-4 && (a_width - b_width).abs() < 1e-4
+    };
+
+    assert!(close(starts[0], (pos2(0.0, 0.0), 1.0)));
+    assert!(close(ends[0], (pos2(6.0, 0.0), 1.6)));
+    assert!(close(starts[1], (pos2(10.0, 0.0), 2.0)));
+    assert!(close(ends[1], (pos2(16.0, 0.0), 2.6)));
+}
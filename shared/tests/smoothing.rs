@@ -0,0 +1,86 @@
+use egui::{pos2, Pos2};
+use shared::{
+    shape::ShapeKind,
+    smoothing::{catmull_rom, moving_average, simplify, Smoothing, StrokeFilter},
+    Line, SPos2,
+};
+
+fn zigzag() -> Vec<Pos2> {
+    (0..=10)
+        .map(|i| pos2(i as f32 * 10.0, if i % 2 == 0 { 0.0 } else { 1.0 }))
+        .collect()
+}
+
+#[test]
+fn moving_average_keeps_the_ends() {
+    let points = zigzag();
+
+    let smoothed = moving_average(&points, 3);
+
+    assert_eq!(smoothed.len(), points.len());
+    assert_eq!(smoothed[0], points[0]);
+    assert_eq!(smoothed[10], points[10]);
+    assert!(smoothed[1..10]
+        .iter()
+        .all(|p| (p.y - 0.5).abs() < 0.2 && p.y != 0.0 && p.y != 1.0));
+}
+
+#[test]
+fn catmull_rom_passes_through_the_coordinates() {
+    let points = [pos2(0.0, 0.0), pos2(10.0, 10.0), pos2(20.0, 0.0)];
+
+    let curve = catmull_rom(&points, 4);
+
+    assert_eq!(curve.len(), 9);
+    assert_eq!(curve[0], points[0]);
+    assert_eq!(curve[4], points[1]);
+    assert_eq!(curve[8], points[2]);
+}
+
+#[test]
+fn simplify_drops_points_within_tolerance() {
+    let points = zigzag();
+
+    assert_eq!(simplify(&points, 2.0), vec![points[0], points[10]]);
+    assert_eq!(simplify(&points, 0.5).len(), points.len());
+    assert_eq!(simplify(&points, 0.0), points);
+
+    let corner = [
+        pos2(0.0, 0.0),
+        pos2(5.0, 0.1),
+        pos2(10.0, 0.0),
+        pos2(10.0, 10.0),
+    ];
+
+    assert_eq!(
+        simplify(&corner, 1.0),
+        vec![corner[0], corner[2], corner[3]]
+    );
+}
+
+#[test]
+fn only_freehand_lines_are_smoothed() {
+    let filter = StrokeFilter {
+        smoothing: Smoothing::CatmullRom,
+        tolerance: 0.1,
+        ..Default::default()
+    };
+
+    let coordinates: Vec<SPos2> = zigzag().into_iter().map(SPos2).collect();
+
+    let mut freehand = Line {
+        coordinates: coordinates.clone(),
+        ..Line::new()
+    };
+    let mut polygon = Line {
+        coordinates: coordinates.clone(),
+        shape: ShapeKind::Polygon { fill: None },
+        ..Line::new()
+    };
+
+    freehand.smooth(&filter);
+    polygon.smooth(&filter);
+
+    assert_ne!(freehand.coordinates, coordinates);
+    assert_eq!(polygon.coordinates, coordinates);
+}
@@ -0,0 +1,90 @@
+use egui::{pos2, Pos2};
+use shared::{
+    color::Color,
+    style::{dashes, extend_ends, Cap, Pattern, StrokeStyle},
+};
+
+#[test]
+fn dashes_follow_corners() {
+    let points = [pos2(0.0, 0.0), pos2(4.0, 0.0), pos2(4.0, 6.0)];
+
+    let dashes = dashes(&points, 3.0, 2.0);
+
+    assert_eq!(
+        dashes,
+        vec![
+            vec![pos2(0.0, 0.0), pos2(3.0, 0.0)],
+            vec![pos2(4.0, 1.0), pos2(4.0, 4.0)],
+            vec![pos2(4.0, 6.0), pos2(4.0, 6.0)],
+        ]
+    );
+}
+
+#[test]
+fn dots_are_single_positions() {
+    let points = [pos2(0.0, 0.0), pos2(5.0, 0.0)];
+
+    let dots: Vec<Pos2> = dashes(&points, 0.0, 2.0)
+        .into_iter()
+        .map(|dot| {
+            assert!(dot.iter().all(|p| *p == dot[0]));
+            dot[0]
+        })
+        .collect();
+
+    assert_eq!(dots, vec![pos2(0.0, 0.0), pos2(2.0, 0.0), pos2(4.0, 0.0)]);
+}
+
+#[test]
+fn dash_array_excludes_caps() {
+    let style = |pattern, cap| StrokeStyle {
+        pattern,
+        cap,
+        ..Default::default()
+    };
+
+    assert_eq!(style(Pattern::Solid, Cap::Round).dash_array(2.0), None);
+    assert_eq!(
+        style(Pattern::Dashed, Cap::Butt).dash_array(2.0),
+        Some([6.0, 4.0])
+    );
+    assert_eq!(
+        style(Pattern::Dashed, Cap::Square).dash_array(2.0),
+        Some([4.0, 6.0])
+    );
+    assert_eq!(
+        style(Pattern::Dotted, Cap::Butt).dash_array(2.0),
+        Some([0.0, 4.0])
+    );
+}
+
+#[test]
+fn opacity_and_highlighter_scale_alpha() {
+    let color = Color::from_rgba_unmultiplied(10, 20, 30, 200);
+
+    let style = StrokeStyle {
+        opacity: 0.5,
+        ..Default::default()
+    };
+
+    assert_eq!(style.paint_color(color).a, 100);
+
+    let highlighter = StrokeStyle {
+        highlighter: true,
+        ..style
+    };
+
+    assert_eq!(
+        highlighter.paint_color(color),
+        Color::from_rgba_unmultiplied(10, 20, 30, 50)
+    );
+}
+
+#[test]
+fn square_caps_extend_ends() {
+    let mut points = [pos2(0.0, 0.0), pos2(10.0, 0.0), pos2(10.0, 10.0)];
+
+    extend_ends(&mut points, 1.0);
+
+    assert_eq!(points, [pos2(-1.0, 0.0), pos2(10.0, 0.0), pos2(10.0, 11.0)]);
+}
@@ -0,0 +1,58 @@
+use egui::pos2;
+use shared::{
+    color::Color,
+    shape::ShapeKind,
+    text::{Text, DEFAULT_FONT_SIZE},
+    Line, Lines, SPos2, StrokeX,
+};
+
+fn text_line(content: &str) -> Line {
+    Line {
+        stroke: StrokeX::new(1.0, Color::BLACK),
+        shape: ShapeKind::Text(Text {
+            content: content.to_string(),
+            ..Text::new(SPos2(pos2(100.0, 50.0)), DEFAULT_FONT_SIZE)
+        }),
+        ..Line::new()
+    }
+}
+
+#[test]
+fn rows_stack_below_the_anchor() {
+    let line = text_line("Goblins\nTrap");
+    let text = line.text().unwrap();
+
+    let rect = line.bounding_rect();
+
+    assert_eq!(rect.min, pos2(100.0, 50.0));
+    assert_eq!(rect.height(), 2.0 * text.row_height());
+    assert!(rect.width() > text_line("Trap").bounding_rect().width());
+
+    let glyphs = text.glyphs();
+
+    assert_eq!(glyphs.len(), "GoblinsTrap".len());
+    assert_eq!(glyphs[7].origin.y - glyphs[0].origin.y, text.row_height());
+}
+
+#[test]
+fn text_is_hit_inside_its_rect() {
+    let line = text_line("Goblins");
+
+    assert!(line.is_drawable());
+    assert!(line.hit_test(pos2(110.0, 60.0), 0.0));
+    assert!(line.hit_test(pos2(99.0, 60.0), 2.0));
+    assert!(!line.hit_test(pos2(90.0, 60.0), 2.0));
+
+    assert!(!text_line(" \n").is_drawable());
+}
+
+#[test]
+fn text_survives_a_round_trip() {
+    let lines = Lines([(3, text_line("Dragon & <hoard>"))].into());
+
+    let json = serde_json::to_string(&lines).unwrap();
+
+    let loaded: Lines = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(loaded[&3].shape, lines[&3].shape);
+}